@@ -0,0 +1,74 @@
+use crate::changelogs::Migration;
+use crate::error::Error;
+use crate::naming::Version;
+
+/// Migrations in changelog order up to and including migration [up_to].
+/// Migration is matched by version, full name or file name, the first one in changelog order is used.
+pub fn select_up_to<'a>(
+    migrations: &[&'a Migration],
+    up_to: &str,
+) -> anyhow::Result<Vec<&'a Migration>, Error> {
+    let position = migrations
+        .iter()
+        .position(|m| {
+            (m.version.is_some() && m.version == Version::parse(up_to))
+                || m.name == up_to
+                || m.name.ends_with(&*format!("/{}", up_to))
+        })
+        .ok_or_else(|| {
+            Error::BaselineError(format!("Migration {} is not found on disk.", up_to))
+        })?;
+    Ok(migrations[..=position].to_vec())
+}
+
+#[cfg(test)]
+mod local_test {
+    use crate::baseline::select_up_to;
+    use crate::changelogs::Migration;
+    use crate::error::Error;
+    use crate::test_utils::MigrationBuilder;
+
+    fn migration(name: &str, order: i64) -> Migration {
+        MigrationBuilder::new(name).order(order).build()
+    }
+
+    #[test]
+    pub fn select_baseline() -> anyhow::Result<()> {
+        let m1 = migration("./migration/1.init.sql", 1);
+        let m2 = migration("./migration/2.update.sql", 2);
+        let m3 = migration("./migration/1.sub/1.new.sql", 1);
+        let migrations = vec![&m1, &m2, &m3];
+
+        let names = |selected: Vec<&Migration>| -> Vec<String> {
+            selected.iter().map(|m| m.name.to_owned()).collect()
+        };
+        assert_eq!(
+            vec![m1.name.to_owned(), m2.name.to_owned()],
+            names(select_up_to(&migrations, "2")?)
+        );
+        assert_eq!(3, select_up_to(&migrations, "1.sub/1.new.sql")?.len());
+        assert_eq!(1, select_up_to(&migrations, "1")?.len());
+        assert!(matches!(
+            select_up_to(&migrations, "4"),
+            Err(Error::BaselineError(_))
+        ));
+
+        // Flyway migrations V1_1 and V1_2 have the same order, they are matched by version.
+        let v1 = MigrationBuilder::new("./migration/V1_1__init.sql")
+            .version("1.1")
+            .build();
+        let v2 = MigrationBuilder::new("./migration/V1_2__update.sql")
+            .version("1.2")
+            .build();
+        // Repeatable migration has no version.
+        let repeatable = MigrationBuilder::new("./migration/R__view.sql")
+            .version("")
+            .build();
+        let migrations = vec![&v1, &v2, &repeatable];
+        assert_eq!(2, select_up_to(&migrations, "1_2")?.len());
+        assert_eq!(1, select_up_to(&migrations, "1.1")?.len());
+        assert!(select_up_to(&migrations, "1").is_err());
+        assert!(select_up_to(&migrations, "0").is_err());
+        Ok(())
+    }
+}
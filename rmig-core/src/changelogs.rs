@@ -1,8 +1,11 @@
+use crate::checksum::{self, Checksum, CHECKSUM, CHECKSUM_NORMALIZE, CHECKSUM_TEMPLATE};
 use crate::driver::Driver;
 use crate::error::Error;
+use crate::naming::{MigrationFile, NamingStrategy, Version};
+use crate::splitter::{self, Dialect, QUERY_SPLITTER};
 use crate::tera_manager::TeraManager;
 use glob::glob;
-use log::{debug, trace};
+use log::{debug, error, trace};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, VecDeque};
 use std::path::PathBuf;
@@ -14,19 +17,24 @@ pub struct Changelogs {
     properties: HashMap<String, String>,
 }
 
-// TODO: Delete author.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Changelog {
     /// Migration name
     pub name: String,
-    /// Migration
+    /// Position of stage in execution order, after sorting by [depends_on]
     #[serde(skip_serializing, skip_deserializing)]
     pub order: i16,
+    /// Stages, which must be executed before this one
+    #[serde(default)]
+    pub depends_on: Vec<String>,
     /// Directory. Support wildcard
     pub directory: String,
+    /// Naming scheme of migration files in directory
+    #[serde(default)]
+    pub naming: NamingStrategy,
     #[serde(skip_serializing, skip_deserializing)]
     pub _directory: Directory,
-    /// Author name
+    /// Author name, written to core table with every migration of stage
     pub author: Option<String>,
 }
 
@@ -45,6 +53,15 @@ impl Directory {
             _directory: None,
         }
     }
+
+    /// All migrations in execution order: files of current directory first, after that sub directories.
+    pub fn migrations(&self) -> Vec<&Migration> {
+        let mut list = self.migration_list.iter().collect::<Vec<&Migration>>();
+        if let Some(dirs) = self._directory.as_ref() {
+            dirs.iter().for_each(|d| list.extend(d.migrations()));
+        }
+        list
+    }
 }
 
 impl FromStr for Directory {
@@ -69,18 +86,59 @@ impl Default for Directory {
 pub struct Migration {
     /// Migration name
     pub name: String,
-    /// Hash MD5
+    /// Hash by algorithm of property [checksum], MD5 by default
     pub hash: String,
+    /// Hash MD5 of rendered text without normalization, written by previous versions.
+    /// Hash of core table is replaced by [hash] with rehash command.
+    #[serde(default)]
+    pub legacy_hash: String,
     /// Separator for split query
     pub separator: String,
     /// Execution order
     pub order: i64,
+    /// Full version of naming scheme, [None] for repeatable migration.
+    #[serde(default)]
+    pub version: Option<Version>,
     /// Query's
     pub query_list: VecDeque<Query>,
+    /// Undo query's, executed by rollback command in reverse order of migrations.
+    pub down_query_list: VecDeque<Query>,
+    /// Name of changelog (stage), written to core table.
+    #[serde(default)]
+    pub stage: Option<String>,
+    /// Author of changelog, written to core table.
+    #[serde(default)]
+    pub author: Option<String>,
     /// Global migration options
     pub options: Option<QueryOptions>,
 }
 
+impl Migration {
+    /// Migration is executed on every run, see [QueryOptions::run_always].
+    pub fn is_run_always(&self) -> bool {
+        self.query_list.iter().any(|q| q.opts.is_run_always())
+    }
+
+    /// Migration is executed again, when hash has been changed, see [QueryOptions::run_on_change].
+    /// Migrations with [run_always] are executed on change too.
+    pub fn is_run_on_change(&self) -> bool {
+        self.is_run_always() || self.query_list.iter().any(|q| q.opts.is_run_on_change())
+    }
+
+    /// Column VERSION of core table, see [Version::sort_key].
+    pub fn version_key(&self) -> Option<String> {
+        self.version.as_ref().map(|v| v.sort_key())
+    }
+
+    /// Transaction mode of the first query, where it is set, see [QueryOptions::transaction].
+    pub fn transaction(&self) -> TransactionMode {
+        self.query_list
+            .iter()
+            .find_map(|q| q.opts.transaction.clone())
+            .unwrap_or_default()
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Query {
     pub query: String,
@@ -90,13 +148,26 @@ pub struct Query {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct QueryOptions {
     pub has_run: Option<bool>,
+    /// Migration is executed on every run (repeatable migration).
     pub run_always: Option<bool>,
+    /// Migration is executed again and hash in core table is updated, when hash has been changed.
+    pub run_on_change: Option<bool>,
+    /// Transaction of migration, default [TransactionMode::PerMigration].
+    pub transaction: Option<TransactionMode>,
     pub global: Option<bool>,
 }
 
 impl QueryOptions {
     pub fn is_global(&self) -> bool {
-        return self.global.as_ref().is_some();
+        self.global.unwrap_or(false)
+    }
+
+    pub fn is_run_always(&self) -> bool {
+        self.run_always.unwrap_or(false)
+    }
+
+    pub fn is_run_on_change(&self) -> bool {
+        self.run_on_change.unwrap_or(false)
     }
 }
 
@@ -105,15 +176,50 @@ impl Default for QueryOptions {
         QueryOptions {
             has_run: Some(false),
             run_always: Some(false),
+            run_on_change: Some(false),
+            transaction: None,
             global: Some(false),
         }
     }
 }
 
+/// Transaction of migration, option [transaction] of query.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionMode {
+    /// Queries of migration are executed in one transaction.
+    PerMigration,
+    /// Queries are executed one by one without transaction (autocommit).
+    /// Used for statements like CREATE INDEX CONCURRENTLY or VACUUM.
+    #[serde(rename = "none")]
+    Autocommit,
+    /// Pending migrations of changelog (stage) are executed in one transaction, all or nothing.
+    PerChangelog,
+}
+
+impl Default for TransactionMode {
+    fn default() -> Self {
+        TransactionMode::PerMigration
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct ChangelogReader<'a> {
     separator: &'a str,
     params: Option<HashMap<String, String>>,
+    /// Built-in splitter of queries, property [query_splitter].
+    splitter: Option<Dialect>,
+    /// Naming scheme of changelog, which is read now.
+    naming: NamingStrategy,
+    /// Name and author of changelog, which is read now.
+    stage: Option<String>,
+    author: Option<String>,
+    /// Algorithm of hash, property [checksum].
+    checksum: Checksum,
+    /// Text is normalized before hashing, property [checksum_normalize].
+    normalize: bool,
+    /// Hash is computed from template before rendering, property [checksum_template].
+    template: bool,
 }
 
 impl<'a> Default for ChangelogReader<'a> {
@@ -121,6 +227,13 @@ impl<'a> Default for ChangelogReader<'a> {
         ChangelogReader {
             separator: "-->",
             params: None,
+            splitter: None,
+            naming: NamingStrategy::default(),
+            stage: None,
+            author: None,
+            checksum: Checksum::default(),
+            normalize: false,
+            template: false,
         }
     }
 }
@@ -130,6 +243,13 @@ impl<'a> ChangelogReader<'a> {
         ChangelogReader {
             separator,
             params: None,
+            splitter: None,
+            naming: NamingStrategy::default(),
+            stage: None,
+            author: None,
+            checksum: Checksum::default(),
+            normalize: false,
+            template: false,
         }
     }
 
@@ -155,7 +275,22 @@ impl<'a> ChangelogReader<'a> {
             x.extend(changelogs.clone().properties);
             self.params = Some(x);
         }
+        self.splitter = self
+            .property(&changelogs, QUERY_SPLITTER)
+            .map(|d| Dialect::from_str(d))
+            .transpose()?;
+        self.checksum = self
+            .property(&changelogs, CHECKSUM)
+            .map(|c| Checksum::from_str(c))
+            .transpose()?
+            .unwrap_or_default();
+        self.normalize = self.flag(&changelogs, CHECKSUM_NORMALIZE)?;
+        self.template = self.flag(&changelogs, CHECKSUM_TEMPLATE)?;
+
         for c in changelogs.changelogs.iter_mut() {
+            self.naming = c.naming.clone();
+            self.stage = Some(c.name.clone());
+            self.author = c.author.clone();
             let dir = self.read_directory(Directory::from_str(&c.directory)?)?;
             c._directory = dir;
         }
@@ -163,44 +298,86 @@ impl<'a> ChangelogReader<'a> {
         Ok(changelogs)
     }
 
+    /// Property of env [--env/-e] or changelog.
+    fn property<'b>(&'b self, changelogs: &'b Changelogs, name: &str) -> Option<&'b String> {
+        self.params
+            .as_ref()
+            .and_then(|p| p.get(name))
+            .or_else(|| changelogs.properties.get(name))
+    }
+
+    /// Boolean property of env [--env/-e] or changelog, false by default.
+    fn flag(&self, changelogs: &Changelogs, name: &str) -> anyhow::Result<bool, Error> {
+        self.property(changelogs, name)
+            .map(|f| {
+                bool::from_str(f.trim()).map_err(|_| {
+                    Error::ParseFileError(format!("Unknown {} '{}'. Use true or false.", name, f))
+                })
+            })
+            .transpose()
+            .map(|f| f.unwrap_or(false))
+    }
+
+    /// Read migrations and sub directories of [dir], both are sorted by numeric order from name prefix.
+    /// Migrations are sorted by version of naming scheme, repeatable migrations are the last.
+    /// Glob returns paths in lexicographic order, it is kept for equal orders and directories without order.
     pub fn read_directory(&self, mut dir: Directory) -> anyhow::Result<Directory, Error> {
         let paths = glob(&dir.name).map_err(|e| Error::IOError(e.msg.to_owned()))?;
+        let mut migrations = Vec::<(MigrationFile, Migration)>::new();
+        let mut sub_directories = Vec::<(Option<i64>, Directory)>::new();
         for path in paths {
             let path_buf = path.expect("Error while getting file path.");
             debug!("Including path's: {:?}", path_buf);
             if !path_buf.is_dir() {
+                let file = self.naming.parse(&path_buf)?;
+                // Undo script is read together with migration.
+                if let MigrationFile::Undo(_) = file {
+                    debug!(
+                        "Skipping undo script {:?}, it is read with its migration.",
+                        path_buf
+                    );
+                    continue;
+                }
                 let migration = self.clone().read_migration(&path_buf)?;
-                // FIXME: Impl normal by order
-                dir.migration_list.push_back(migration)
+                migrations.push((file, migration))
             } else {
                 let x = path_buf.to_str().expect("File path is not readable.");
                 let sub_directory =
                     self.read_directory(Directory::from_str(format!("{}{}", x, "/*").as_str())?)?;
+                sub_directories.push((order_of_directory(&path_buf), sub_directory));
+            }
+        }
 
-                fn create_directory(dir: Directory) -> Option<Box<VecDeque<Directory>>> {
-                    let mut deque = VecDeque::<Directory>::new();
-                    deque.push_back(dir);
-                    return Some(Box::new(deque));
+        migrations.sort_by(|a, b| a.0.cmp(&b.0));
+        let duplicate = migrations
+            .windows(2)
+            .find_map(|w| match (&w[0].0, &w[1].0) {
+                (MigrationFile::Versioned(a), MigrationFile::Versioned(b)) if a == b => {
+                    Some((a, &w[0].1.name, &w[1].1.name))
                 }
+                _ => None,
+            });
+        if let Some((version, first, second)) = duplicate {
+            return Err(Error::DuplicateOrderError(format!(
+                "Migrations {} and {} have the same order {}.",
+                first, second, version
+            )));
+        }
+        dir.migration_list = migrations.into_iter().map(|(_, m)| m).collect();
 
-                //Посмотреть что тут за пиздец
-                dir._directory = Some(
-                    dir._directory
-                        .as_mut()
-                        .map(|d| {
-                            d.push_back(sub_directory.clone());
-                            d
-                        })
-                        .unwrap_or(&mut create_directory(sub_directory.clone()).unwrap())
-                        .to_owned(),
-                );
-            }
+        // Directories without order are read after ordered ones.
+        sub_directories.sort_by_key(|(order, _)| (order.is_none(), *order));
+        if !sub_directories.is_empty() {
+            dir._directory = Some(Box::new(
+                sub_directories.into_iter().map(|(_, d)| d).collect(),
+            ));
         }
         Ok(dir)
     }
 
     pub fn read_migration(self, path: &PathBuf) -> anyhow::Result<Migration, Error> {
         let mut sql = std::fs::read_to_string(path).map_err(|e| Error::IOError(e.to_string()))?;
+        let template = sql.clone();
 
         let name = path
             .file_name()
@@ -214,28 +391,69 @@ impl<'a> ChangelogReader<'a> {
                 .apply(&*name, sql.as_str())?;
         }
 
-        let hash = format!("{:x}", md5::compute(&sql));
-        let name_separate = name.trim().split('.').collect::<Vec<&str>>();
-        // TODO: Add order to version or remove.
-        let mut order = 0i64;
-        // Extension .sql/.any
-        if name_separate.len() > 1 {
-            order = name_separate[0]
-                .parse()
-                .map_err(|_e| Error::ParseFileError("File name is not contains order. Please use format order.filename.any extension. For example: 1.init.sql".to_owned()))?;
+        // Undo section is not included in hash, it can be fixed after migration is applied.
+        let (up, down) = split_down_section(&*sql);
+        let down = match down {
+            Some(down) => Some(down),
+            None => self.read_down_file(path, &*name)?,
+        };
+
+        let legacy_hash = format!("{:x}", md5::compute(&up));
+        // Hash of template does not depend on env, rendered text is executed anyway.
+        let hashed = if self.template {
+            split_down_section(&*template).0
         } else {
-            return Err(Error::ParseError(
-                name,
-                "Error while read migration. Extension required.".to_string(),
-            ));
+            up.clone()
+        };
+        let hash = if self.normalize {
+            self.checksum
+                .compute(&*checksum::normalize(&*hashed, self.separator))
+        } else {
+            self.checksum.compute(&*hashed)
+        };
+        let file = self.naming.parse(path)?;
+        // ORDER_ID of Flyway migration is the major part of version, it is not changed by new
+        // migrations with lower version. Full version is written to column VERSION.
+        let version = match &file {
+            MigrationFile::Versioned(v) | MigrationFile::Undo(v) => Some(v.clone()),
+            MigrationFile::Repeatable => None,
+        };
+        let order = version.as_ref().map_or(0, |v| v.major());
+
+        debug!("Reading migration by file: {}", &name);
+        let mut querys = self.read_queries(&*up)?;
+        if file == MigrationFile::Repeatable {
+            querys
+                .iter_mut()
+                .for_each(|q| q.opts.run_on_change = Some(true));
         }
+        let down_querys = match down {
+            Some(down) => self.read_queries(&*down)?,
+            None => VecDeque::new(),
+        };
 
+        Ok(Migration {
+            name: path.as_path().to_str().map(|m| m.to_owned()).unwrap(),
+            hash,
+            legacy_hash,
+            separator: self.separator.to_string(),
+            order,
+            version,
+            query_list: querys,
+            down_query_list: down_querys,
+            stage: self.stage.clone(),
+            author: self.author.clone(),
+            options: None,
+        })
+    }
+
+    /// Split text by separator, global options of query are applied to next queries.
+    fn read_queries(&self, sql: &str) -> anyhow::Result<VecDeque<Query>, Error> {
         // Collections separating lines
-        let query = sql.as_str().split(&self.separator).collect::<Vec<&str>>();
+        let query = sql.split(&self.separator).collect::<Vec<&str>>();
 
         let mut querys = VecDeque::<Query>::with_capacity(query.len());
 
-        debug!("Reading migration by file: {}", &name);
         let mut global_options_for_query = None::<QueryOptions>;
         // Надо дописать нормально обработку options.
         for q in query {
@@ -247,17 +465,40 @@ impl<'a> ChangelogReader<'a> {
             if global_options_for_query.as_ref().is_some() {
                 qu.opts = global_options_for_query.as_ref().unwrap().clone();
             }
-            querys.push_back(qu)
+            match self.splitter.as_ref() {
+                // Every statement is a query with options of the part.
+                Some(dialect) => {
+                    splitter::split(&*qu.query, dialect)
+                        .into_iter()
+                        .for_each(|statement| {
+                            querys.push_back(Query {
+                                query: statement,
+                                opts: qu.opts.clone(),
+                            })
+                        })
+                }
+                None => querys.push_back(qu),
+            }
         }
+        Ok(querys)
+    }
 
-        Ok(Migration {
-            name: path.as_path().to_str().map(|m| m.to_owned()).unwrap(),
-            hash,
-            separator: self.separator.to_string(),
-            order,
-            query_list: querys,
-            options: None,
-        })
+    /// Read undo script next to migration file, if exists. See [NamingStrategy::undo_file_name].
+    fn read_down_file(&self, path: &PathBuf, name: &str) -> anyhow::Result<Option<String>, Error> {
+        let down_path = match self.naming.undo_file_name(name) {
+            Some(down_name) => path.with_file_name(down_name),
+            None => return Ok(None),
+        };
+        if !down_path.is_file() {
+            return Ok(None);
+        }
+        let mut sql =
+            std::fs::read_to_string(&down_path).map_err(|e| Error::IOError(e.to_string()))?;
+        if self.params.as_ref().is_some() {
+            sql = TeraManager::new(self.params.as_ref().unwrap().clone())
+                .apply(&*format!("{}.down", name), sql.as_str())?;
+        }
+        Ok(Some(sql))
     }
 
     pub fn read_options(&self, text: &str) -> anyhow::Result<Option<QueryOptions>, Error> {
@@ -299,6 +540,44 @@ impl<'a> ChangelogReader<'a> {
     }
 }
 
+/// Line, which splits migration file on migration and undo section.
+pub const DOWN_SECTION: &str = "--rmig-down--";
+
+/// Split sql by [DOWN_SECTION] line on migration and undo script.
+fn split_down_section(sql: &str) -> (String, Option<String>) {
+    let mut up = Vec::<&str>::new();
+    let mut down = None::<Vec<&str>>;
+    for line in sql.lines() {
+        match down.as_mut() {
+            Some(d) => d.push(line),
+            None if line.trim() == DOWN_SECTION => down = Some(vec![]),
+            None => up.push(line),
+        }
+    }
+    match down {
+        // Text without undo section is not changed, hash of applied migrations is the same.
+        None => (sql.to_string(), None),
+        Some(d) => (up.join("\n"), Some(d.join("\n"))),
+    }
+}
+
+/// Numeric prefix of directory name [order.name], if exists.
+fn order_of_directory(path: &PathBuf) -> Option<i64> {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .and_then(|n| n.split('.').next())
+        .and_then(|o| o.parse().ok())
+}
+
+/// Undo script has name [order.name.down.ext]. Migration with name [down] ([3.down.sql]) is not undo script.
+pub(crate) fn is_down_file(path: &PathBuf) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .map(|n| n.split('.').collect::<Vec<&str>>())
+        .map(|s| s.len() >= 4 && s[s.len() - 2] == "down" && s[0].parse::<u64>().is_ok())
+        .unwrap_or(false)
+}
+
 pub struct ChangelogRunner<'a> {
     pub changelog: Changelogs,
     pub datasources: Vec<&'a Box<dyn Driver>>,
@@ -310,50 +589,388 @@ impl<'a> ChangelogRunner<'a> {
         changelog_path: String,
         datasources: Vec<&'a Box<dyn Driver>>,
         properties: Option<HashMap<String, String>>,
-    ) -> Self {
+    ) -> anyhow::Result<Self, Error> {
         let changelog_reader = properties
             .as_ref()
             .and_then(|p| p.get("query_separator"))
             .map(|path| ChangelogReader::new(path.as_str()))
             .unwrap_or_default();
-        ChangelogRunner {
-            changelog: changelog_reader
-                .read_changelog_with_env(changelog_path.to_owned(), properties.clone())
-                .unwrap_or_else(|e| {
-                    panic!(
-                        "Error while reading changelog with name {}",
-                        &*changelog_path
-                    )
-                }),
+        let changelog = changelog_reader
+            .read_changelog_with_env(changelog_path.to_owned(), properties.clone())
+            .map_err(|e| {
+                error!(
+                    "Error while reading changelog with name {}",
+                    &*changelog_path
+                );
+                e
+            })?;
+        Ok(ChangelogRunner {
+            changelog,
             datasources,
             properties,
-        }
+        })
     }
 
-    pub fn filter_by_stage(mut self, stages: Vec<String>) -> Self {
-        if !stages.is_empty() {
-            debug!(
-                "Starting filtering changelog, current size: {}",
-                &self.changelog.changelogs.len()
-            );
-            self.changelog.changelogs = self
-                .changelog
-                .changelogs
-                .into_iter()
-                .filter(|n| {
-                    trace!("Filtering changelog ");
-                    stages.contains(&n.name)
-                })
-                .collect();
-            debug!("After filtering size: {}", &self.changelog.changelogs.len());
+    /// Keep changelogs of [stages] with all their [depends_on] dependencies, and sort them, so every
+    /// stage is executed after its dependencies. Without stages all changelogs are kept.
+    /// Stages without dependencies keep order of changelog file.
+    pub fn filter_by_stage(mut self, stages: Vec<String>) -> anyhow::Result<Self, Error> {
+        debug!(
+            "Starting filtering changelog, current size: {}",
+            &self.changelog.changelogs.len()
+        );
+        let changelogs = std::mem::take(&mut self.changelog.changelogs);
+        let mut sorted = Vec::<usize>::new();
+        let mut path = Vec::<usize>::new();
+        for (i, c) in changelogs.iter().enumerate() {
+            if stages.is_empty() || stages.contains(&c.name) {
+                trace!("Sorting dependencies of stage {}", &c.name);
+                sort_by_dependencies(i, &changelogs, &mut path, &mut sorted)?;
+            }
         }
-        self
+        self.changelog.changelogs = sorted
+            .into_iter()
+            .enumerate()
+            .map(|(order, i)| {
+                let mut c = changelogs[i].clone();
+                c.order = order as i16;
+                c
+            })
+            .collect();
+        debug!("After filtering size: {}", &self.changelog.changelogs.len());
+        Ok(self)
+    }
+}
+
+/// Depth-first visit of stage dependencies, stage is added to [sorted] after all of them.
+/// [path] holds stages of the current visit to detect cycles.
+fn sort_by_dependencies(
+    index: usize,
+    changelogs: &[Changelog],
+    path: &mut Vec<usize>,
+    sorted: &mut Vec<usize>,
+) -> anyhow::Result<(), Error> {
+    if sorted.contains(&index) {
+        return Ok(());
+    }
+    if let Some(p) = path.iter().position(|i| *i == index) {
+        let cycle = path[p..]
+            .iter()
+            .chain(std::iter::once(&index))
+            .map(|i| changelogs[*i].name.to_owned())
+            .collect::<Vec<String>>();
+        return Err(Error::StageDependencyError(format!(
+            "Cycle between stages: {}.",
+            cycle.join(" -> ")
+        )));
     }
+    path.push(index);
+    let changelog = &changelogs[index];
+    for dependency in changelog.depends_on.iter() {
+        let i = changelogs
+            .iter()
+            .position(|c| c.name.eq(dependency))
+            .ok_or_else(|| {
+                Error::StageDependencyError(format!(
+                    "Stage {} depends on unknown stage {}.",
+                    &changelog.name, dependency
+                ))
+            })?;
+        sort_by_dependencies(i, changelogs, path, sorted)?;
+    }
+    path.pop();
+    sorted.push(index);
+    Ok(())
 }
 
 /// TODO: Write tests
 #[cfg(test)]
 mod local_test {
+    use crate::changelogs::{
+        is_down_file, split_down_section, Changelog, ChangelogReader, ChangelogRunner, Changelogs,
+        Directory, TransactionMode,
+    };
+    use crate::checksum::Checksum;
+    use crate::error::Error;
+    use crate::naming::NamingStrategy;
+    use crate::test_utils::TempDir;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    fn runner(stages: Vec<(&str, Vec<&str>)>) -> ChangelogRunner<'static> {
+        ChangelogRunner {
+            changelog: Changelogs {
+                changelogs: stages
+                    .into_iter()
+                    .map(|(name, depends_on)| Changelog {
+                        name: name.to_string(),
+                        order: 0,
+                        depends_on: depends_on.into_iter().map(|d| d.to_string()).collect(),
+                        directory: "./migration".to_string(),
+                        naming: Default::default(),
+                        _directory: Directory::new("./migration".to_string()),
+                        author: None,
+                    })
+                    .collect(),
+                properties: HashMap::new(),
+            },
+            datasources: vec![],
+            properties: None,
+        }
+    }
+
+    fn names(runner: ChangelogRunner) -> Vec<String> {
+        runner
+            .changelog
+            .changelogs
+            .into_iter()
+            .map(|c| c.name)
+            .collect()
+    }
+
+    #[test]
+    pub fn read_directory_by_order() -> anyhow::Result<()> {
+        let root = TempDir::new("rmig_order")?;
+        let write = |path: &str| root.write(path, "select 1;");
+        write("10.last.sql")?;
+        write("2.second.sql")?;
+        write("1.first.sql")?;
+        write("10.sub_last/1.a.sql")?;
+        write("2.sub_second/1.a.sql")?;
+        write("other/1.a.sql")?;
+
+        let reader = ChangelogReader::default();
+        let dir = reader.read_directory(Directory::new(root.glob()))?;
+        let names = dir
+            .migrations()
+            .iter()
+            .map(|m| {
+                m.name
+                    .trim_start_matches(&*root.path.display().to_string())
+                    .to_owned()
+            })
+            .collect::<Vec<String>>();
+        assert_eq!(
+            vec![
+                "/1.first.sql",
+                "/2.second.sql",
+                "/10.last.sql",
+                "/2.sub_second/1.a.sql",
+                "/10.sub_last/1.a.sql",
+                "/other/1.a.sql",
+            ],
+            names
+        );
+
+        write("2.duplicate.sql")?;
+        let result = reader.read_directory(Directory::new(root.glob()));
+        assert!(matches!(result, Err(Error::DuplicateOrderError(_))));
+        Ok(())
+    }
+
+    #[test]
+    pub fn read_flyway_directory() -> anyhow::Result<()> {
+        let root = TempDir::new("rmig_flyway")?;
+        for name in vec![
+            "V2__second.sql",
+            "V1_2__first.sql",
+            "R__view.sql",
+            "U2__second.sql",
+        ] {
+            root.write(name, "select 1;")?;
+        }
+        let mut reader = ChangelogReader::default();
+        reader.naming = NamingStrategy::Flyway;
+        let read = |reader: &ChangelogReader| -> anyhow::Result<Vec<(String, i64)>, Error> {
+            let dir = reader.read_directory(Directory::new(root.glob()))?;
+            Ok(dir
+                .migrations()
+                .iter()
+                .map(|m| (m.name.rsplit('/').next().unwrap().to_owned(), m.order))
+                .collect())
+        };
+
+        assert_eq!(
+            vec![
+                ("V1_2__first.sql".to_string(), 1),
+                ("V2__second.sql".to_string(), 2),
+                ("R__view.sql".to_string(), 0),
+            ],
+            read(&reader)?
+        );
+        // Migration with lower version does not change ORDER_ID of others.
+        root.write("V1_1__added.sql", "select 1;")?;
+        assert_eq!(
+            vec![
+                ("V1_1__added.sql".to_string(), 1),
+                ("V1_2__first.sql".to_string(), 1),
+                ("V2__second.sql".to_string(), 2),
+                ("R__view.sql".to_string(), 0),
+            ],
+            read(&reader)?
+        );
+        let dir = reader.read_directory(Directory::new(root.glob()))?;
+        let versions = dir
+            .migrations()
+            .iter()
+            .map(|m| m.version.as_ref().map(|v| v.to_string()))
+            .collect::<Vec<Option<String>>>();
+        assert_eq!(
+            vec![
+                Some("1.1".to_string()),
+                Some("1.2".to_string()),
+                Some("2".to_string()),
+                None
+            ],
+            versions
+        );
+        Ok(())
+    }
+
+    #[test]
+    pub fn sort_stages_by_dependencies() -> anyhow::Result<()> {
+        let stages = vec![
+            ("reporting", vec!["billing", "core"]),
+            ("billing", vec!["core"]),
+            ("core", vec![]),
+            ("audit", vec![]),
+        ];
+
+        assert_eq!(
+            vec!["core", "billing", "reporting", "audit"],
+            names(runner(stages.clone()).filter_by_stage(vec![])?)
+        );
+        // Dependencies are pulled in.
+        let filtered = runner(stages.clone()).filter_by_stage(vec!["reporting".to_string()])?;
+        assert_eq!(
+            vec![0, 1, 2],
+            filtered
+                .changelog
+                .changelogs
+                .iter()
+                .map(|c| c.order)
+                .collect::<Vec<i16>>()
+        );
+        assert_eq!(vec!["core", "billing", "reporting"], names(filtered));
+
+        assert_eq!(
+            Err(Error::StageDependencyError(
+                "Cycle between stages: a -> b -> a.".to_string()
+            )),
+            runner(vec![("a", vec!["b"]), ("b", vec!["a"])])
+                .filter_by_stage(vec![])
+                .map(names)
+        );
+        assert_eq!(
+            Err(Error::StageDependencyError(
+                "Stage a depends on unknown stage c.".to_string()
+            )),
+            runner(vec![("a", vec!["c"])])
+                .filter_by_stage(vec![])
+                .map(names)
+        );
+        Ok(())
+    }
+
+    #[test]
+    pub fn read_query_options() -> anyhow::Result<()> {
+        let reader = ChangelogReader::default();
+        let querys = reader.read_queries(
+            "create table a(id int);\n-->\n--rmig--{\"run_on_change\":true}\ncreate view v as select 1;",
+        )?;
+        assert_eq!(2, querys.len());
+        assert!(!querys[0].opts.is_run_on_change());
+        assert!(querys[1].opts.is_run_on_change());
+        assert_eq!("create view v as select 1;", querys[1].query);
+
+        // Global options are applied to next queries.
+        let querys = reader.read_queries(
+            "--rmig--{\"run_always\":true,\"global\":true}\ncreate view a as select 1;\n-->\ncreate view b as select 1;",
+        )?;
+        assert!(querys.iter().all(|q| q.opts.is_run_always()));
+
+        let querys = reader.read_queries(
+            "--rmig--{\"transaction\":\"none\"}\ncreate index concurrently i on a(id);",
+        )?;
+        assert_eq!(
+            Some(TransactionMode::Autocommit),
+            querys[0].opts.transaction
+        );
+        Ok(())
+    }
+
+    #[test]
+    pub fn split_down() -> anyhow::Result<()> {
+        let sql = "create table a(id int);\n--rmig-down--\ndrop table a;\n";
+        let (up, down) = split_down_section(sql);
+        assert_eq!("create table a(id int);", up);
+        assert_eq!(Some("drop table a;".to_string()), down);
+
+        let sql = "create table a(id int);\n";
+        assert_eq!((sql.to_string(), None), split_down_section(sql));
+
+        assert!(is_down_file(&PathBuf::from("./migration/1.init.down.sql")));
+        assert!(!is_down_file(&PathBuf::from("./migration/1.init.sql")));
+        assert!(!is_down_file(&PathBuf::from("./migration/3.down.sql")));
+        assert!(!is_down_file(&PathBuf::from("./migration/init.down.sql")));
+        Ok(())
+    }
+
+    #[test]
+    pub fn read_migration_checksum() -> anyhow::Result<()> {
+        let root = TempDir::new("rmig_checksum")?;
+        let unix = root.write("1.unix.sql", "create table a(id int);\n")?;
+        let windows = root.write(
+            "2.windows.sql",
+            "-- Table a\r\ncreate table a(id int); \r\n",
+        )?;
+
+        let mut reader = ChangelogReader::default();
+        let legacy = reader.clone().read_migration(&unix)?;
+        assert_eq!(legacy.legacy_hash, legacy.hash);
+
+        reader.checksum = Checksum::Sha256;
+        reader.normalize = true;
+        let unix = reader.clone().read_migration(&unix)?;
+        let windows = reader.read_migration(&windows)?;
+        assert_eq!(unix.hash, windows.hash);
+        assert_eq!(64, unix.hash.len());
+        assert_eq!(legacy.legacy_hash, unix.legacy_hash);
+        assert_ne!(unix.legacy_hash, windows.legacy_hash);
+        Ok(())
+    }
+
+    #[test]
+    pub fn read_migration_template_checksum() -> anyhow::Result<()> {
+        let root = TempDir::new("rmig_template")?;
+        let path = root.write(
+            "1.init.sql",
+            "create table {{ schema }}.a(id int);\n--rmig-down--\ndrop table {{ schema }}.a;\n",
+        )?;
+        let read = |schema: &str, template: bool| {
+            let mut env = HashMap::new();
+            env.insert("schema".to_string(), schema.to_string());
+            let mut reader = ChangelogReader::default();
+            reader.params = Some(env);
+            reader.template = template;
+            reader.read_migration(&path)
+        };
+
+        let dev = read("dev", true)?;
+        let prod = read("prod", true)?;
+        assert_eq!(dev.hash, prod.hash);
+        assert_eq!("create table prod.a(id int);", prod.query_list[0].query);
+        assert_eq!("drop table prod.a;", prod.down_query_list[0].query);
+        assert_eq!(
+            Checksum::Md5.compute("create table {{ schema }}.a(id int);"),
+            prod.hash
+        );
+        // Rendered text is hashed by default.
+        let rendered = read("prod", false)?;
+        assert_ne!(dev.hash, rendered.hash);
+        assert_eq!(prod.legacy_hash, rendered.hash);
+        Ok(())
+    }
 
     #[test]
     pub fn test_md5() -> anyhow::Result<()> {
@@ -0,0 +1,159 @@
+use crate::enum_str;
+use crate::error::Error;
+use sha2::{Digest, Sha256};
+use std::str::FromStr;
+
+/// Property of changelog or env [--env/-e], algorithm of migration hash.
+pub const CHECKSUM: &str = "checksum";
+
+/// Property of changelog or env [--env/-e], text of migration is normalized before hashing.
+pub const CHECKSUM_NORMALIZE: &str = "checksum_normalize";
+
+/// Property of changelog or env [--env/-e], hash is computed from template of migration before
+/// rendering, so it does not depend on values of env.
+pub const CHECKSUM_TEMPLATE: &str = "checksum_template";
+
+/// Query options and undo section are kept by normalization, they change execution of migration.
+const RMIG_PREFIX: &str = "--rmig";
+
+enum_str! {
+    pub enum Checksum {
+        Md5 = 0x00000,
+        Sha256 = 0x00001,
+        Crc32 = 0x00002,
+    }
+}
+
+impl Checksum {
+    /// Hash of text in lowercase hex.
+    pub fn compute(&self, text: &str) -> String {
+        match self {
+            Checksum::Md5 => format!("{:x}", md5::compute(text)),
+            Checksum::Sha256 => format!("{:x}", Sha256::digest(text.as_bytes())),
+            Checksum::Crc32 => format!("{:08x}", crc32fast::hash(text.as_bytes())),
+        }
+    }
+}
+
+impl Default for Checksum {
+    /// MD5 is used by previous versions, hashes of core table stay valid.
+    fn default() -> Self {
+        Checksum::Md5
+    }
+}
+
+impl FromStr for Checksum {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let checksum = s.trim().to_lowercase();
+        vec![Checksum::Md5, Checksum::Sha256, Checksum::Crc32]
+            .into_iter()
+            .find(|c| c.name().to_lowercase().eq(&checksum))
+            .ok_or_else(|| {
+                Error::ParseFileError(format!(
+                    "Unknown {} '{}'. Use md5, sha256 or crc32.",
+                    CHECKSUM, s
+                ))
+            })
+    }
+}
+
+/// Text of migration without changes, which do not change queries: line endings are [\n], trailing
+/// whitespace of lines, blank lines and SQL comments outside of string literals are removed.
+/// Query options, undo section and [separator] of queries are not comments and are kept.
+pub fn normalize(sql: &str, separator: &str) -> String {
+    let sql = sql.replace("\r\n", "\n").replace('\r', "\n");
+    let bytes = sql.as_bytes();
+    let mut text = String::with_capacity(sql.len());
+    let mut start = 0usize;
+    let mut i = 0usize;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            // Options of query follow the prefix on the same line, the whole line is kept.
+            b'-' if sql[i..].starts_with(RMIG_PREFIX) => {
+                i = sql[i..].find('\n').map_or(bytes.len(), |n| i + n);
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') && !sql[i..].starts_with(separator) => {
+                text.push_str(&sql[start..i]);
+                i = sql[i..].find('\n').map_or(bytes.len(), |n| i + n);
+                start = i;
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                text.push_str(&sql[start..i]);
+                i = sql[i + 2..].find("*/").map_or(bytes.len(), |n| i + n + 4);
+                start = i;
+            }
+            // Doubled quote inside of literal is read as two adjacent literals.
+            quote @ b'\'' | quote @ b'"' => {
+                i = sql[i + 1..]
+                    .find(quote as char)
+                    .map_or(bytes.len(), |n| i + n + 2)
+            }
+            _ => i += 1,
+        }
+    }
+    text.push_str(&sql[start..]);
+
+    text.lines()
+        .map(|line| line.trim_end())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod local_test {
+    use crate::checksum::{normalize, Checksum};
+    use std::str::FromStr;
+
+    #[test]
+    pub fn compute_checksum() -> anyhow::Result<()> {
+        assert_eq!(
+            "5eb63bbbe01eeed093cb22bb8f5acdc3",
+            Checksum::Md5.compute("hello world")
+        );
+        assert_eq!(
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9",
+            Checksum::Sha256.compute("hello world")
+        );
+        assert_eq!("0d4a1185", Checksum::Crc32.compute("hello world"));
+        assert_eq!(Checksum::Sha256, Checksum::from_str(" SHA256 ")?);
+        assert!(Checksum::from_str("sha1").is_err());
+        Ok(())
+    }
+
+    #[test]
+    pub fn normalize_text() -> anyhow::Result<()> {
+        let unix = "--rmig--{\"global\":true}\n\
+                    create table a(id int, name text default '--not comment');\n\
+                    -->\n\
+                    insert into a values (1, 'it''s /* text */');";
+        let windows = "-- Table of users\r\n\
+                       --rmig--{\"global\":true}  \r\n\
+                       create table a(id int, name text default '--not comment'); /* key */ -- name\r\n\
+                       \r\n\
+                       -->\r\n\
+                       /* Multiline\r\n\
+                       comment */\r\n\
+                       insert into a values (1, 'it''s /* text */');\t\r\n";
+        assert_eq!(unix, normalize(windows, "-->"));
+        assert_eq!(normalize(unix, "-->"), normalize(windows, "-->"));
+        // Options of query and undo marker change the hash.
+        assert_ne!(
+            normalize("--rmig--{\"global\":true}\nselect 1;", "-->"),
+            normalize("--rmig--{\"global\":false}\nselect 1;", "-->")
+        );
+        assert_eq!(
+            "select 1;\n--rmig-down--\nselect 2;",
+            normalize("select 1;\n--rmig-down--\nselect 2;", "-->")
+        );
+        // Whitespace inside of line is not changed.
+        assert_ne!(
+            normalize("select 'a  b';", "-->"),
+            normalize("select 'a b';", "-->")
+        );
+        Ok(())
+    }
+}
@@ -1,24 +1,43 @@
-use crate::changelogs::{Changelog, ChangelogRunner, Directory};
+use crate::baseline::select_up_to;
+use crate::changelogs::{Changelog, ChangelogRunner, Directory, Migration, TransactionMode};
 use crate::configuration_properties::{DatasourceProperties, DatasourcesProperties};
-use crate::driver::{DatasourceFactory, Driver, RmigEmptyResult};
+use crate::driver::{AppliedMigration, DatasourceFactory, Driver, Execution, RmigEmptyResult};
 use crate::enum_str;
 use crate::error::Error;
+use crate::history::{HistoryImport, HistorySource};
+use crate::plan::{PlanReport, Step};
+use crate::rehash::RehashPlan;
+use crate::repair::RepairPlan;
+use crate::report::{DatasourceReport, MigrationOutcome, RunReport};
+use crate::rollback::{find_down_migrations, RollbackTarget};
+use crate::status::StatusReport;
 use crate::tera_manager::TeraManager;
 use clap::{load_yaml, App, ArgMatches};
-use futures::executor::block_on;
 use log::LevelFilter;
-use log::{error, info};
+use log::{error, info, warn};
 use log4rs::append::console::{ConsoleAppender, Target};
 use log4rs::config::{Appender, Root};
 use log4rs::{Config, Handle};
-use std::borrow::Borrow;
 use std::collections::HashMap;
+use std::future::Future;
+use std::io::Write;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
 
 enum_str! {
  pub enum Command {
     Status = 0x00000,
     Run = 0x00001,
+    Script = 0x00002,
+    Rollback = 0x00003,
+    ImportHistory = 0x00004,
+    Baseline = 0x00005,
+    Repair = 0x00006,
+    Rehash = 0x00007,
  }
 }
 
@@ -26,13 +45,31 @@ impl FromStr for Command {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let command = s.to_lowercase();
+        // Subcommand [import-history] is [ImportHistory].
+        let command = s.to_lowercase().replace('-', "");
         return if Command::Status.name().to_lowercase().eq(command.as_str()) {
             Ok(Command::Status)
-        } else {
+        } else if Command::Run.name().to_lowercase().eq(command.as_str()) {
             Ok(Command::Run)
+        } else if Command::Script.name().to_lowercase().eq(command.as_str()) {
+            Ok(Command::Script)
+        } else if Command::Rollback.name().to_lowercase().eq(command.as_str()) {
+            Ok(Command::Rollback)
+        } else if Command::ImportHistory
+            .name()
+            .to_lowercase()
+            .eq(command.as_str())
+        {
+            Ok(Command::ImportHistory)
+        } else if Command::Baseline.name().to_lowercase().eq(command.as_str()) {
+            Ok(Command::Baseline)
+        } else if Command::Repair.name().to_lowercase().eq(command.as_str()) {
+            Ok(Command::Repair)
+        } else if Command::Rehash.name().to_lowercase().eq(command.as_str()) {
+            Ok(Command::Rehash)
+        } else {
+            Err(Error::NotFoundCommand(s.to_string()))
         };
-        // Err(Error::NotFoundCommand(s.to_string()))
     }
 }
 
@@ -74,6 +111,14 @@ pub struct CliArgs {
     config: Option<String>,
     stage: Option<Vec<String>>,
     properties: Option<HashMap<String, String>>,
+    parallel: Option<usize>,
+    fail_fast: bool,
+    dry_run: bool,
+    out: Option<String>,
+    rollback: Option<RollbackTarget>,
+    history: Option<HistorySource>,
+    up_to: Option<String>,
+    yes: bool,
 }
 
 impl Default for CliArgs {
@@ -85,6 +130,14 @@ impl Default for CliArgs {
             config: None,
             stage: None,
             properties: None,
+            parallel: None,
+            fail_fast: false,
+            dry_run: false,
+            out: None,
+            rollback: None,
+            history: None,
+            up_to: None,
+            yes: false,
         }
     }
 }
@@ -137,42 +190,140 @@ impl CliReader {
 
     /// Read properties [--url]
     pub fn read_url(mut self) -> CliReader {
-        if let Some(c) = self.args.command.as_ref() {
-            if Command::Run == c.clone() {
-                if let Some(m) = self
-                    .args_match
-                    .subcommand_matches(Command::Run.name().to_lowercase())
-                {
-                    m.value_of("url")
-                        .map(|arg| String::from(arg))
-                        .map(|arg| self.args.url.insert(arg));
-                }
-            }
+        let url = self
+            .command_matches()
+            .and_then(|m| m.value_of("url"))
+            .map(|arg| String::from(arg));
+        if url.is_some() {
+            self.args.url = url;
         }
         self
     }
 
     /// Read properties [--stage/-s]
     pub fn read_stage(mut self) -> CliReader {
-        if let Some(c) = self.args.command.as_ref() {
-            if Command::Run == c.clone() {
-                if let Some(m) = self
-                    .args_match
-                    .subcommand_matches(Command::Run.name().to_lowercase())
-                {
-                    let stage = m.values_of("stage").map(|value| {
-                        value
-                            .into_iter()
-                            .map(|v| String::from(v))
-                            .collect::<Vec<String>>()
-                    });
-                    self.args.stage = stage;
-                }
-            }
-        }
+        let stage = self
+            .command_matches()
+            .and_then(|m| m.values_of("stage"))
+            .map(|value| {
+                value
+                    .into_iter()
+                    .map(|v| String::from(v))
+                    .collect::<Vec<String>>()
+            });
+        self.args.stage = stage;
+        self
+    }
+
+    /// Read properties [--parallel], it must be positive number.
+    pub fn read_parallel(mut self) -> anyhow::Result<CliReader, Error> {
+        let parallel = self
+            .command_matches()
+            .and_then(|m| m.value_of("parallel"))
+            .map(|arg| {
+                usize::from_str(arg).ok().filter(|p| *p > 0).ok_or_else(|| {
+                    Error::NotFoundCommand(format!(
+                        "Parallel must be positive number, got '{}'.",
+                        arg
+                    ))
+                })
+            })
+            .transpose()?;
+        self.args.parallel = parallel;
+        Ok(self)
+    }
+
+    /// Read flag [--fail-fast]
+    pub fn read_fail_fast(mut self) -> CliReader {
+        self.args.fail_fast = self
+            .command_matches()
+            .map(|m| m.is_present("fail_fast"))
+            .unwrap_or(false);
+        self
+    }
+
+    /// Read flag [--dry-run]
+    pub fn read_dry_run(mut self) -> CliReader {
+        self.args.dry_run = self
+            .command_matches()
+            .map(|m| m.is_present("dry_run"))
+            .unwrap_or(false);
+        self
+    }
+
+    /// Read properties [--out/-o]
+    pub fn read_out(mut self) -> CliReader {
+        self.args.out = self
+            .command_matches()
+            .and_then(|m| m.value_of("out"))
+            .map(|arg| String::from(arg));
+        self
+    }
+
+    /// Read properties [--to] or [--count] of rollback, count must be positive number.
+    pub fn read_rollback(mut self) -> anyhow::Result<CliReader, Error> {
+        let matches = self.command_matches();
+        let to = matches
+            .and_then(|m| m.value_of("to"))
+            .map(|arg| RollbackTarget::To(String::from(arg)));
+        let count = matches
+            .and_then(|m| m.value_of("count"))
+            .map(|arg| {
+                usize::from_str(arg)
+                    .ok()
+                    .filter(|c| *c > 0)
+                    .map(RollbackTarget::Count)
+                    .ok_or_else(|| {
+                        Error::RollbackError(format!(
+                            "Count must be positive number, got '{}'.",
+                            arg
+                        ))
+                    })
+            })
+            .transpose()?;
+        self.args.rollback = to.or(count);
+        Ok(self)
+    }
+
+    /// Read properties [--from] of import-history
+    pub fn read_history(mut self) -> anyhow::Result<CliReader, Error> {
+        self.args.history = self
+            .command_matches()
+            .and_then(|m| m.value_of("from"))
+            .map(|arg| {
+                HistorySource::from_str(arg).map_err(|_| {
+                    Error::NotFoundCommand("Use --from flyway or liquibase.".to_string())
+                })
+            })
+            .transpose()?;
+        Ok(self)
+    }
+
+    /// Read properties [--up-to] of baseline
+    pub fn read_up_to(mut self) -> CliReader {
+        self.args.up_to = self
+            .command_matches()
+            .and_then(|m| m.value_of("up_to"))
+            .map(|arg| String::from(arg));
         self
     }
 
+    /// Read flag [--yes/-y]
+    pub fn read_yes(mut self) -> CliReader {
+        self.args.yes = self
+            .command_matches()
+            .map(|m| m.is_present("yes"))
+            .unwrap_or(false);
+        self
+    }
+
+    /// Matches of current subcommand [Command enum]
+    fn command_matches(&self) -> Option<&ArgMatches> {
+        self.args_match
+            .subcommand_name()
+            .and_then(|c| self.args_match.subcommand_matches(c))
+    }
+
     /// Read properties [--env/-e]
     pub fn read_properties(mut self) -> CliReader {
         let mut _properties = HashMap::<String, String>::new();
@@ -190,14 +341,24 @@ impl CliReader {
         self
     }
 
-    /// Read all configuration properties and return this instance
-    pub fn read(self) -> CliReader {
-        self.read_command()
+    /// Read all configuration properties and return this instance.
+    /// Invalid values of user input are returned as error.
+    pub fn read(self) -> anyhow::Result<CliReader, Error> {
+        Ok(self
+            .read_command()
             .read_logging_level()
             .read_config()
             .read_stage()
             .read_url()
-            .read_properties()
+            .read_parallel()?
+            .read_fail_fast()
+            .read_dry_run()
+            .read_out()
+            .read_rollback()?
+            .read_history()?
+            .read_up_to()
+            .read_yes()
+            .read_properties())
     }
 
     pub fn args(&self) -> &CliArgs {
@@ -206,12 +367,12 @@ impl CliReader {
 }
 
 impl Default for CliReader {
+    /// Arguments of command line, they are read by [CliReader::read].
     fn default() -> Self {
         CliReader {
             args: CliArgs::default(),
             args_match: Cli::default().get_matches(),
         }
-        .read()
     }
 }
 
@@ -223,151 +384,821 @@ pub struct AppRmigCli {
 }
 
 impl AppRmigCli {
-    pub fn init(self) -> AppRmigCli {
-        self.read_args().logging_level().read_datasource()
+    pub fn init(self) -> anyhow::Result<AppRmigCli, Error> {
+        self.read_args()?.logging_level().read_datasource()
     }
 
-    fn read_args(mut self) -> AppRmigCli {
+    fn read_args(mut self) -> anyhow::Result<AppRmigCli, Error> {
+        self.arg_processor = self.arg_processor.read()?;
         self.args = self.arg_processor.args().to_owned();
-        self
+        Ok(self)
     }
 
     pub async fn execute(&mut self) -> anyhow::Result<(), Error> {
         let command = self.args.command.as_ref().unwrap();
-        return if Command::Run == *command {
-            self.run().await
+        return if Command::Run == *command && self.args.dry_run {
+            self.plan().await
+        } else if Command::Run == *command {
+            let report = self.run().await?;
+            println!("{}", report);
+            report.into_result()
         } else if Command::Status == *command {
             self.status().await
+        } else if Command::Script == *command {
+            self.script().await
+        } else if Command::Rollback == *command {
+            let report = self.rollback().await?;
+            println!("{}", report);
+            report.into_result()
+        } else if Command::ImportHistory == *command {
+            let report = self.import_history().await?;
+            println!("{}", report);
+            report.into_result()
+        } else if Command::Baseline == *command {
+            let report = self.baseline().await?;
+            println!("{}", report);
+            report.into_result()
+        } else if Command::Repair == *command {
+            let report = self.repair().await?;
+            println!("{}", report);
+            report.into_result()
+        } else if Command::Rehash == *command {
+            let report = self.rehash().await?;
+            println!("{}", report);
+            report.into_result()
         } else {
             Err(Error::NotFoundCommand("Command not found.".to_string()))
         };
     }
 
     pub async fn status(&mut self) -> anyhow::Result<(), Error> {
+        let ds_v = self.datasources.iter().collect::<Vec<&Box<dyn Driver>>>();
+        let changelogs = self.read_changelogs(ds_v.clone())?;
+        let migrations = changelogs
+            .iter()
+            .flat_map(|c| c._directory.migrations())
+            .collect::<Vec<&Migration>>();
+
+        for driver in ds_v {
+            // Core table is not created yet, all migrations are pending.
+            let applied = match driver.check_rmig_core_table().await {
+                Ok(_) => {
+                    report_outdated_core_table(driver).await?;
+                    driver.find_all_in_core_table().await?
+                }
+                Err(Error::RowError(_)) => vec![],
+                Err(e) => return Err(e),
+            };
+
+            let mut report = StatusReport::compare(driver.get_name(), migrations.clone(), applied);
+            // Other stages are not read from disk, their rows are not missing.
+            if self.args.stage.is_some() {
+                report = report.without_missing_on_disk();
+            }
+            println!("{}\n", report);
+        }
+
         Ok(())
     }
 
-    pub async fn run(&mut self) -> anyhow::Result<(), Error> {
-        let ds_v = self
-            .datasources
+    /// Print pending queries for all datasources, nothing is executed and core table is not created.
+    pub async fn plan(&mut self) -> anyhow::Result<(), Error> {
+        let ds_v = self.datasources.iter().collect::<Vec<&Box<dyn Driver>>>();
+        let changelogs = self.read_changelogs(ds_v.clone())?;
+        let migrations = changelogs
             .iter()
-            .map(|i| i)
-            .collect::<Vec<&Box<dyn Driver>>>();
-        let props = *&self.args.properties.as_ref().unwrap();
+            .flat_map(|c| c._directory.migrations())
+            .collect::<Vec<&Migration>>();
 
-        let mut future_drivers = Vec::with_capacity(ds_v.len());
+        for driver in ds_v {
+            driver.validate_connection().await?;
+            // Core table is not created yet, all migrations are pending.
+            let applied = match driver.check_rmig_core_table().await {
+                Ok(_) => {
+                    report_outdated_core_table(driver).await?;
+                    driver.find_all_in_core_table().await?
+                }
+                Err(Error::RowError(_)) => vec![],
+                Err(e) => return Err(e),
+            };
 
-        // First action, prepare table if does not exists
-        for driver in &self.datasources {
-            let d = prepare_db(driver);
-            future_drivers.push(d);
+            let report = PlanReport::pending(driver.get_name(), migrations.clone(), applied);
+            println!("{}\n", report);
         }
 
-        let config = &self.args.config.as_ref();
+        Ok(())
+    }
 
-        // If stages is empty, skip filtering.
-        let stages = self.args.stage.clone().unwrap_or(vec![]);
+    /// Write pending migrations of every datasource to standalone sql script, nothing is executed.
+    /// Script includes core table, if it does not exist, and rows of core table for every migration.
+    pub async fn script(&mut self) -> anyhow::Result<(), Error> {
+        let ds_v = self.datasources.iter().collect::<Vec<&Box<dyn Driver>>>();
+        let changelogs = self.read_changelogs(ds_v.clone())?;
+        let migrations = changelogs
+            .iter()
+            .flat_map(|c| c._directory.migrations())
+            .collect::<Vec<&Migration>>();
+        let names = ds_v
+            .iter()
+            .map(|d| d.get_name().to_owned())
+            .collect::<Vec<String>>();
+
+        for (i, driver) in ds_v.iter().enumerate() {
+            driver.validate_connection().await?;
+            let mut lines = vec![format!("-- Datasource: {}", driver.get_name())];
+            let applied = match driver.check_rmig_core_table().await {
+                Ok(_) => {
+                    let upgrades = driver.upgrade_rmig_core_table_sql().await?;
+                    if !upgrades.is_empty() {
+                        lines.push("-- Upgrade of core table".to_string());
+                        lines.extend(upgrades);
+                    }
+                    driver.find_all_in_core_table().await?
+                }
+                Err(Error::RowError(_)) => {
+                    lines.push(format!("{};", driver.rmig_core_table_sql()?.trim()));
+                    vec![]
+                }
+                Err(e) => return Err(e),
+            };
+
+            // Migrations are selected as run command does, their rows of core table are replaced.
+            for m in migrations.iter() {
+                match Step::of(m, &applied) {
+                    Ok(Step::Skip) => {}
+                    Ok(_) => {
+                        lines.push(format!("-- Migration: {} (order {})", &m.name, m.order));
+                        lines.push(driver.script_migration(m));
+                    }
+                    Err(_) => {
+                        warn!(
+                            "Hash of {} has been changed, migration is not included.",
+                            &m.name
+                        );
+                        lines.push(format!("-- WARNING: hash of {} has been changed.", &m.name));
+                    }
+                }
+            }
+            let script = lines.join("\n") + "\n";
 
-        // Awaiting
-        info!("Awaiting all datasources.");
-        for driver in future_drivers {
-            driver.await?;
+            match self.args.out.as_ref() {
+                None => println!("{}", script),
+                Some(out) => {
+                    let path = script_path(out, &names, i);
+                    std::fs::write(&path, script).map_err(|e| Error::IOError(e.to_string()))?;
+                    info!(
+                        "Script for datasource {} is written to {}",
+                        driver.get_name(),
+                        &path
+                    );
+                }
+            }
         }
 
-        let impl_changelogs_from_cfg = if config.is_some() {
-            Ok(ChangelogRunner::new_from_file(
-                config.unwrap().clone(),
-                ds_v.clone(),
-                Some(props.clone()),
-            ))
-        } else {
-            Err(Error::ParseFileError(
-                "File is empty or not readable.".to_string(),
-            ))
-        }?
-        .filter_by_stage(stages.clone());
+        /// One datasource is written to [--out], several are written to [out.name.sql].
+        /// Index is added for datasources with the same name.
+        fn script_path(out: &str, names: &Vec<String>, i: usize) -> String {
+            if names.len() == 1 {
+                return out.to_owned();
+            }
+            let path = std::path::Path::new(out);
+            let stem = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("script");
+            let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("sql");
+            let name = if names.iter().filter(|n| **n == names[i]).count() > 1 {
+                format!("{}.{}", &names[i], i)
+            } else {
+                names[i].to_owned()
+            };
+            path.with_file_name(format!("{}.{}.{}", stem, name, ext))
+                .to_string_lossy()
+                .to_string()
+        }
 
-        let changelogs = impl_changelogs_from_cfg.changelog.changelogs;
+        Ok(())
+    }
+
+    /// Roll back applied migrations by undo scripts in reverse order of execution.
+    /// Every datasource is rolled back only if all undo scripts of selected migrations are found.
+    pub async fn rollback(&mut self) -> anyhow::Result<RunReport, Error> {
+        let target = self.args.rollback.clone().ok_or_else(|| {
+            Error::RollbackError("Use --to <version|name> or --count <N>.".to_string())
+        })?;
+        let ds_v = self.datasources.iter().collect::<Vec<&Box<dyn Driver>>>();
+        let changelogs = self.read_changelogs(ds_v.clone())?;
+        let migrations = changelogs
+            .iter()
+            .flat_map(|c| c._directory.migrations())
+            .collect::<Vec<&Migration>>();
 
-        // TODO: Допилить тут нормальную асинхронщину. Метод нормальный, но по факту внутри ждем постоянно.
-        for changelog in changelogs {
-            run_changelog(&ds_v, changelog).await
+        let mut report = RunReport::default();
+        for driver in ds_v {
+            let mut ds_report = DatasourceReport::new(driver.get_name());
+            let result = with_locked_core_table(driver, false, |applied| {
+                rollback_datasource(driver, &target, &migrations, applied, &mut ds_report)
+            })
+            .await;
+            report.datasources.push(ds_report.with_result(result));
         }
 
-        async fn prepare_db(driver: &Box<dyn Driver>) -> RmigEmptyResult {
-            driver
-                .check_rmig_core_table()
-                .or_else(|_| driver.create_rmig_core_table())
+        async fn rollback_datasource(
+            driver: &Box<dyn Driver>,
+            target: &RollbackTarget,
+            migrations: &Vec<&Migration>,
+            applied: Vec<AppliedMigration>,
+            report: &mut DatasourceReport,
+        ) -> RmigEmptyResult {
+            let targets = target.select(applied)?;
+            let down = find_down_migrations(&targets, migrations)?;
+
+            for m in down {
+                info!("Rollback migration with name: {}", &m.name);
+                let start = Instant::now();
+                let result = match m.transaction() {
+                    TransactionMode::Autocommit => {
+                        match driver
+                            .migrate_without_transaction(m.down_query_list.iter().collect())
+                            .await
+                        {
+                            Ok(_) => driver.delete_from_core_table(m.name.to_owned()).await,
+                            Err(e) => Err(e),
+                        }
+                    }
+                    _ => driver.migrate_down(m).await,
+                };
+                match result {
+                    Ok(_) => report.add(&m.name, MigrationOutcome::RolledBack, start.elapsed()),
+                    Err(e) => {
+                        error!(
+                            "Rollback of {} is failed, next migrations are skipped.",
+                            &m.name
+                        );
+                        report.add(
+                            &m.name,
+                            MigrationOutcome::Failed(e.to_string()),
+                            start.elapsed(),
+                        );
+                        return Ok(());
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        Ok(report)
+    }
+
+    /// Add rows of core table for migrations found in history table of other tool [--from].
+    /// Migrations are not executed, hash is computed from local file. Scripts without local file are skipped.
+    pub async fn import_history(&mut self) -> anyhow::Result<RunReport, Error> {
+        let source =
+            self.args.history.clone().ok_or_else(|| {
+                Error::NotFoundCommand("Use --from flyway or liquibase.".to_string())
+            })?;
+        let ds_v = self.datasources.iter().collect::<Vec<&Box<dyn Driver>>>();
+        let changelogs = self.read_changelogs(ds_v.clone())?;
+        let migrations = changelogs
+            .iter()
+            .flat_map(|c| c._directory.migrations())
+            .collect::<Vec<&Migration>>();
+
+        let mut report = RunReport::default();
+        for driver in ds_v {
+            let mut ds_report = DatasourceReport::new(driver.get_name());
+            let result = with_locked_core_table(driver, true, |applied| {
+                import_datasource(driver, &source, &migrations, applied, &mut ds_report)
+            })
+            .await;
+            report.datasources.push(ds_report.with_result(result));
+        }
+
+        async fn import_datasource(
+            driver: &Box<dyn Driver>,
+            source: &HistorySource,
+            migrations: &Vec<&Migration>,
+            applied: Vec<AppliedMigration>,
+            report: &mut DatasourceReport,
+        ) -> RmigEmptyResult {
+            let scripts = driver.find_in_history_table(source).await?;
+            let import = HistoryImport::new(scripts, migrations, &applied);
+
+            for script in import.unmatched.iter() {
+                warn!(
+                    "Script {} of {:?} history is not found on disk, it is skipped.",
+                    script, source
+                );
+            }
+            for m in import.skipped {
+                report.add(&m.name, MigrationOutcome::Skipped, Duration::default());
+            }
+            for m in import.migrations {
+                info!("Import migration with name: {}", &m.name);
+                let start = Instant::now();
+                match driver
+                    .add_new_migration(m.clone(), Execution::success(Duration::default()))
+                    .await
+                {
+                    Ok(_) => report.add(&m.name, MigrationOutcome::Imported, start.elapsed()),
+                    Err(e) => {
+                        report.add(
+                            &m.name,
+                            MigrationOutcome::Failed(e.to_string()),
+                            start.elapsed(),
+                        );
+                        return Ok(());
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        Ok(report)
+    }
+
+    /// Add rows of core table for migrations up to [--up-to] without execution.
+    /// Already applied migrations are skipped.
+    pub async fn baseline(&mut self) -> anyhow::Result<RunReport, Error> {
+        let up_to = self
+            .args
+            .up_to
+            .clone()
+            .ok_or_else(|| Error::BaselineError("Use --up-to <version|name>.".to_string()))?;
+        let ds_v = self.datasources.iter().collect::<Vec<&Box<dyn Driver>>>();
+        let changelogs = self.read_changelogs(ds_v.clone())?;
+        let migrations = changelogs
+            .iter()
+            .flat_map(|c| c._directory.migrations())
+            .collect::<Vec<&Migration>>();
+        let selected = select_up_to(&migrations, &*up_to)?;
+
+        let mut report = RunReport::default();
+        for driver in ds_v {
+            let mut ds_report = DatasourceReport::new(driver.get_name());
+            let result = with_locked_core_table(driver, true, |applied| {
+                baseline_datasource(driver, &selected, applied, &mut ds_report)
+            })
+            .await;
+            report.datasources.push(ds_report.with_result(result));
+        }
+
+        async fn baseline_datasource(
+            driver: &Box<dyn Driver>,
+            migrations: &Vec<&Migration>,
+            applied: Vec<AppliedMigration>,
+            report: &mut DatasourceReport,
+        ) -> RmigEmptyResult {
+            for m in migrations {
+                if applied.iter().any(|a| a.name == m.name) {
+                    report.add(&m.name, MigrationOutcome::Skipped, Duration::default());
+                    continue;
+                }
+                info!("Baseline migration with name: {}", &m.name);
+                let start = Instant::now();
+                match driver
+                    .add_new_migration((*m).clone(), Execution::success(Duration::default()))
+                    .await
+                {
+                    Ok(_) => report.add(&m.name, MigrationOutcome::Baselined, start.elapsed()),
+                    Err(e) => {
+                        report.add(
+                            &m.name,
+                            MigrationOutcome::Failed(e.to_string()),
+                            start.elapsed(),
+                        );
+                        return Ok(());
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        Ok(report)
+    }
+
+    /// Update changed hashes and delete rows of migrations, which do not exist on disk.
+    /// Changes of every datasource are printed and applied after confirmation or with [--yes].
+    pub async fn repair(&mut self) -> anyhow::Result<RunReport, Error> {
+        let ds_v = self.datasources.iter().collect::<Vec<&Box<dyn Driver>>>();
+        let changelogs = self.read_changelogs(ds_v.clone())?;
+        let migrations = changelogs
+            .iter()
+            .flat_map(|c| c._directory.migrations())
+            .collect::<Vec<&Migration>>();
+        // Other stages are not read from disk, their rows are not deleted.
+        let filtered = self.args.stage.is_some();
+        let yes = self.args.yes;
+
+        let mut report = RunReport::default();
+        for driver in ds_v {
+            let mut ds_report = DatasourceReport::new(driver.get_name());
+            // Core table is not created yet, nothing to repair.
+            let result = with_locked_core_table(driver, false, |applied| {
+                repair_datasource(driver, &migrations, applied, filtered, yes, &mut ds_report)
+            })
+            .await;
+            report.datasources.push(ds_report.with_result(result));
         }
 
-        async fn run_changelog(driver: &Vec<&Box<dyn Driver>>, changelog: Changelog) {
-            driver.iter().for_each(|d| {
-                let md = *d;
+        async fn repair_datasource(
+            driver: &Box<dyn Driver>,
+            migrations: &Vec<&Migration>,
+            applied: Vec<AppliedMigration>,
+            filtered: bool,
+            yes: bool,
+            report: &mut DatasourceReport,
+        ) -> RmigEmptyResult {
+            let mut status = StatusReport::compare(driver.get_name(), migrations.clone(), applied);
+            if filtered {
+                status = status.without_missing_on_disk();
+            }
+            let plan = RepairPlan::new(&status, migrations);
+            println!("{}\n", plan);
+            if plan.is_empty() || !(yes || confirm(driver.get_name())?) {
+                return Ok(());
+            }
+
+            for m in plan.updates {
+                info!("Update hash of migration with name: {}", &m.name);
+                let start = Instant::now();
+                match driver.update_hash_in_core_table(m.clone()).await {
+                    Ok(_) => report.add(&m.name, MigrationOutcome::Repaired, start.elapsed()),
+                    Err(e) => {
+                        report.add(
+                            &m.name,
+                            MigrationOutcome::Failed(e.to_string()),
+                            start.elapsed(),
+                        );
+                        return Ok(());
+                    }
+                }
+            }
+            for name in plan.deletes {
+                info!("Delete row of migration with name: {}", &name);
+                let start = Instant::now();
+                match driver.delete_from_core_table(name.to_owned()).await {
+                    Ok(_) => report.add(&name, MigrationOutcome::Removed, start.elapsed()),
+                    Err(e) => {
+                        report.add(
+                            &name,
+                            MigrationOutcome::Failed(e.to_string()),
+                            start.elapsed(),
+                        );
+                        return Ok(());
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        Ok(report)
+    }
+
+    /// Replace hashes of previous versions with hashes of current properties [checksum],
+    /// [checksum_normalize] and [checksum_template]. Changes are applied after confirmation or with [--yes].
+    pub async fn rehash(&mut self) -> anyhow::Result<RunReport, Error> {
+        let ds_v = self.datasources.iter().collect::<Vec<&Box<dyn Driver>>>();
+        let changelogs = self.read_changelogs(ds_v.clone())?;
+        let migrations = changelogs
+            .iter()
+            .flat_map(|c| c._directory.migrations())
+            .collect::<Vec<&Migration>>();
+        let yes = self.args.yes;
+
+        let mut report = RunReport::default();
+        for driver in ds_v {
+            let mut ds_report = DatasourceReport::new(driver.get_name());
+            // Core table is not created yet, nothing to rehash.
+            let result = with_locked_core_table(driver, false, |applied| {
+                rehash_datasource(driver, &migrations, applied, yes, &mut ds_report)
+            })
+            .await;
+            report.datasources.push(ds_report.with_result(result));
+        }
+
+        async fn rehash_datasource(
+            driver: &Box<dyn Driver>,
+            migrations: &Vec<&Migration>,
+            applied: Vec<AppliedMigration>,
+            yes: bool,
+            report: &mut DatasourceReport,
+        ) -> RmigEmptyResult {
+            let plan = RehashPlan::new(driver.get_name(), migrations, &applied);
+            println!("{}\n", plan);
+            for name in plan.modified.iter() {
+                warn!(
+                    "Migration {} has been changed after it was applied, use repair command.",
+                    name
+                );
+            }
+            if plan.is_empty() || !(yes || confirm(driver.get_name())?) {
+                return Ok(());
+            }
+
+            for m in plan.updates {
+                info!("Rehash migration with name: {}", &m.name);
+                let start = Instant::now();
+                match driver.update_hash_in_core_table(m.clone()).await {
+                    Ok(_) => report.add(&m.name, MigrationOutcome::Rehashed, start.elapsed()),
+                    Err(e) => {
+                        report.add(
+                            &m.name,
+                            MigrationOutcome::Failed(e.to_string()),
+                            start.elapsed(),
+                        );
+                        return Ok(());
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        Ok(report)
+    }
+
+    /// Read changelog from [--config/-c], filter it by [--stage/-s] and sort by [depends_on]
+    fn read_changelogs(
+        &self,
+        datasources: Vec<&Box<dyn Driver>>,
+    ) -> anyhow::Result<Vec<Changelog>, Error> {
+        let config =
+            self.args.config.as_ref().ok_or_else(|| {
+                Error::ParseFileError("File is empty or not readable.".to_string())
+            })?;
+
+        // If stages is empty, skip filtering.
+        let stages = self.args.stage.clone().unwrap_or(vec![]);
+
+        Ok(ChangelogRunner::new_from_file(
+            config.clone(),
+            datasources,
+            self.args.properties.clone(),
+        )?
+        .filter_by_stage(stages)?
+        .changelog
+        .changelogs)
+    }
+
+    /// Run migrations for all datasources.
+    /// Return error only if changelog is not readable, results of datasources are collected in [RunReport].
+    pub async fn run(&mut self) -> anyhow::Result<RunReport, Error> {
+        let ds_v = self.datasources.iter().collect::<Vec<&Box<dyn Driver>>>();
+
+        let changelogs = Arc::new(self.read_changelogs(ds_v)?);
+        let parallel = self.args.parallel.unwrap_or(1);
+        let fail_fast = self.args.fail_fast;
+
+        // Every datasource is migrated by independent task, at most [--parallel] at the same time.
+        // Failed datasource with [--fail-fast] stops tasks before the next changelog, locks are released.
+        info!("Awaiting all datasources. Parallel: {}", parallel);
+        let permits = Arc::new(Semaphore::new(parallel));
+        let aborted = Arc::new(AtomicBool::new(false));
+        let tasks = std::mem::take(&mut self.datasources)
+            .into_iter()
+            .map(|driver| {
+                let changelogs = changelogs.clone();
+                let permits = permits.clone();
+                let aborted = aborted.clone();
+                tokio::spawn(async move {
+                    // Semaphore is not closed, permit is always acquired.
+                    let _permit = permits.acquire().await.ok();
+                    let report = run_datasource(&driver, &changelogs, &aborted).await;
+                    if report.is_success() {
+                        info!("Datasource {} is migrated.", &report.datasource);
+                    } else {
+                        error!("Datasource {} is failed.", &report.datasource);
+                        if fail_fast && !aborted.swap(true, Ordering::SeqCst) {
+                            warn!(
+                                "Fail fast is enabled, migration of other datasources is aborted."
+                            );
+                        }
+                    }
+                    (driver, report)
+                })
+            })
+            .collect::<Vec<JoinHandle<(Box<dyn Driver>, DatasourceReport)>>>();
+
+        let mut report = RunReport::default();
+        for task in tasks {
+            let (driver, ds_report) = task.await.map_err(|e| {
+                Error::DatasourcesFailed(format!("Task of datasource is not completed. {}", e))
+            })?;
+            self.datasources.push(driver);
+            report.datasources.push(ds_report);
+        }
+
+        /// Pipeline of one datasource: prepare core table, after that run changelogs by order.
+        /// The first failed migration stops datasource, [aborted] stops it before the next changelog.
+        async fn run_datasource(
+            md: &Box<dyn Driver>,
+            changelogs: &Vec<Changelog>,
+            aborted: &AtomicBool,
+        ) -> DatasourceReport {
+            let mut report = DatasourceReport::new(md.get_name());
+            if aborted.load(Ordering::SeqCst) {
+                report.error =
+                    Some("Aborted by --fail-fast, datasource is not migrated.".to_string());
+                return report;
+            }
+            if let Err(e) = prepare_db(md).await {
+                return report.with_error(e);
+            }
+            for (i, changelog) in changelogs.iter().enumerate() {
+                if aborted.load(Ordering::SeqCst) {
+                    report.error = Some(format!(
+                        "Aborted by --fail-fast, {} of {} changelogs are executed.",
+                        i,
+                        changelogs.len()
+                    ));
+                    return report;
+                }
                 // Waiting lock.
                 // If lock is already exists, we await.
                 // Or else, lock session, and go migration.
-                block_on(md.lock()).unwrap();
-                read_dir(md, Box::new(changelog._directory.borrow())).unwrap();
-
-                fn read_dir(
-                    md: &Box<dyn Driver>,
-                    _directory: Box<&Directory>,
-                ) -> anyhow::Result<(), Error> {
-                    // Run current migration list.
-                    _directory.migration_list.iter().for_each(|m| {
-                        let hash = &m.hash;
-                        let name = &m.name;
-
-                        match md.find_in_core_table(name.to_string(), hash.to_string()) {
-                            Ok(_) => {
-                                info!(
-                                    "Migration with {} with hash {} is already exists.",
-                                    &*name, &*hash
-                                );
-                            }
-                            Err(e) => match e {
-                                // Ignoring other error's.
-                                Error::NotFoundCommand(_) => {}
-                                Error::CreatingDatasourceError(_) => {}
-                                Error::LoggerConfigurationError(_) => {}
-                                Error::ParseError(_, _) => {}
-                                Error::IOError(_) => {}
-                                Error::ParseFileError(_) => {}
-                                Error::ConnectionValidationError(_) => {}
-                                /////////////////////////////////////
-                                Error::SQLError(s) => {
-                                    error!("Connection not stable, or query error.");
-                                    panic!("{}", s);
-                                }
-                                Error::HashUniqueError(s) => {
-                                    // panic, hash has been changed.
-                                    error!("Hash has been changed.");
-                                    panic!("{}", s);
-                                }
-                                Error::RowError(_s) => {
-                                    // Row not found. Add new migration.
-                                    info!("Run new migration with name: {}", &*m.name);
-                                    // Panic and rollback transaction.
-                                    md.migrate(m.query_list.iter().map(|i| i).collect())
-                                        .unwrap();
-                                    block_on(md.add_new_migration(m.clone())).unwrap();
-                                }
-                            },
-                        }
-                    });
+                if let Err(e) = md.lock().await {
+                    return report.with_error(e);
+                }
+                let per_changelog = changelog
+                    ._directory
+                    .migrations()
+                    .iter()
+                    .any(|m| m.transaction() == TransactionMode::PerChangelog);
+                // Rows of core table are read under lock, migrations of changelog are compared with them.
+                match md.find_all_in_core_table().await {
+                    Ok(applied) if per_changelog => {
+                        migrate_changelog(md, &changelog._directory, &applied, &mut report).await
+                    }
+                    Ok(applied) => {
+                        migrate_directory(md, &changelog._directory, &applied, &mut report).await
+                    }
+                    Err(e) => report = report.with_error(e),
+                }
+                if let Err(e) = md.unlock().await {
+                    return report.with_error(e);
+                }
+                if !report.is_success() {
+                    break;
+                }
+            }
+            report
+        }
+
+        /// Core table is created or upgraded under lock, before the first changelog.
+        async fn prepare_db(driver: &Box<dyn Driver>) -> RmigEmptyResult {
+            driver.validate_connection().await?;
+            driver.lock().await?;
+            let result = match driver.check_rmig_core_table().await {
+                Ok(_) => driver.upgrade_rmig_core_table().await,
+                Err(_) => driver.create_rmig_core_table().await,
+            };
+            driver.unlock().await.and(result)
+        }
 
-                    if _directory._directory.is_some() {
-                        let dirs = _directory._directory.as_ref().unwrap();
-                        dirs.as_ref()
-                            .iter()
-                            .for_each(|d| read_dir(md, Box::new(d)).unwrap());
+        async fn migrate_directory(
+            md: &Box<dyn Driver>,
+            _directory: &Directory,
+            applied: &[AppliedMigration],
+            report: &mut DatasourceReport,
+        ) {
+            // Run migrations of directory and sub directories.
+            for m in _directory.migrations() {
+                let start = Instant::now();
+
+                let outcome = match next_step(m, applied) {
+                    Ok(Step::Skip) => MigrationOutcome::Skipped,
+                    Ok(_) => match execute_step(md, m).await {
+                        Ok(_) => MigrationOutcome::Applied,
+                        Err(e) => MigrationOutcome::Failed(e.to_string()),
+                    },
+                    Err(e) => MigrationOutcome::Failed(e.to_string()),
+                };
+
+                let failed = outcome.is_failed();
+                report.add(&m.name, outcome, start.elapsed());
+                if failed {
+                    error!(
+                        "Migration {} is failed, next migrations are skipped.",
+                        &*m.name
+                    );
+                    return;
+                }
+            }
+        }
+
+        /// Pending migrations of changelog with [per_changelog] transaction are executed in one
+        /// transaction with rows of core table: all of them are applied or nothing.
+        async fn migrate_changelog(
+            md: &Box<dyn Driver>,
+            _directory: &Directory,
+            applied: &[AppliedMigration],
+            report: &mut DatasourceReport,
+        ) {
+            let start = Instant::now();
+            let mut pending = Vec::<&Migration>::new();
+            for m in _directory.migrations() {
+                let step = match m.transaction() {
+                    TransactionMode::Autocommit => Err(Error::ParseFileError(
+                        "Transaction none is not allowed in changelog with per_changelog transaction."
+                            .to_string(),
+                    )),
+                    _ => next_step(m, applied),
+                };
+                match step {
+                    Ok(Step::Skip) => {
+                        report.add(&m.name, MigrationOutcome::Skipped, start.elapsed())
+                    }
+                    Ok(_) => pending.push(m),
+                    Err(e) => {
+                        error!(
+                            "Migration {} is failed, changelog is not executed.",
+                            &*m.name
+                        );
+                        report.add(
+                            &m.name,
+                            MigrationOutcome::Failed(e.to_string()),
+                            start.elapsed(),
+                        );
+                        return;
                     }
-                    Ok(())
                 }
-            });
+            }
+            if pending.is_empty() {
+                return;
+            }
+
+            info!("Run {} migrations in one transaction.", pending.len());
+            let outcome = match md.migrate(pending.clone()).await {
+                Ok(_) => MigrationOutcome::Applied,
+                Err(e) => {
+                    error!("Transaction of changelog is rolled back.");
+                    for m in pending.iter() {
+                        add_failed_migration(md, m, start.elapsed()).await;
+                    }
+                    MigrationOutcome::Failed(e.to_string())
+                }
+            };
+            for m in pending {
+                report.add(&m.name, outcome.clone(), start.elapsed());
+            }
         }
 
-        Ok(())
+        /// Decide by rows of core table, if migration must be executed.
+        fn next_step(m: &Migration, applied: &[AppliedMigration]) -> anyhow::Result<Step, Error> {
+            let name = &m.name;
+            let step = Step::of(m, applied);
+            match step {
+                Ok(Step::Repeat) => info!("Run migration with option run_always: {}", &*name),
+                Ok(Step::Skip) => info!(
+                    "Migration with {} with hash {} is already exists.",
+                    &*name, &*m.hash
+                ),
+                // Row not found. Add new migration.
+                Ok(Step::New) => info!("Run new migration with name: {}", &*name),
+                Ok(Step::Changed) => {
+                    info!("Hash has been changed, run migration again: {}", &*name)
+                }
+                Err(_) => error!("Hash has been changed."),
+            }
+            step
+        }
+
+        /// Run queries by transaction mode of migration and replace its row of core table.
+        /// Failed execution is written to core table with status FAILED.
+        async fn execute_step(md: &Box<dyn Driver>, m: &Migration) -> RmigEmptyResult {
+            let start = Instant::now();
+            let result = if m.transaction() != TransactionMode::Autocommit {
+                // Row of core table is written in the same transaction, rollback on error.
+                md.migrate(vec![m]).await
+            } else {
+                match md
+                    .migrate_without_transaction(m.query_list.iter().collect())
+                    .await
+                {
+                    Ok(_) => {
+                        md.add_new_migration(m.clone(), Execution::success(start.elapsed()))
+                            .await
+                    }
+                    Err(e) => Err(e),
+                }
+            };
+            if result.is_err() {
+                add_failed_migration(md, m, start.elapsed()).await;
+            }
+            result
+        }
+
+        /// Error of core table is logged, error of migration is reported.
+        async fn add_failed_migration(md: &Box<dyn Driver>, m: &Migration, elapsed: Duration) {
+            if let Err(e) = md
+                .add_new_migration(m.clone(), Execution::failed(elapsed))
+                .await
+            {
+                warn!("Failed execution of {} is not recorded. {}", &m.name, e);
+            }
+        }
+
+        Ok(report)
     }
 
     fn logging_level(mut self) -> AppRmigCli {
@@ -398,7 +1229,7 @@ impl AppRmigCli {
         self
     }
 
-    fn read_datasource(mut self) -> AppRmigCli {
+    fn read_datasource(mut self) -> anyhow::Result<AppRmigCli, Error> {
         let datasources = match self.args.config.as_ref() {
             None => read_datasource_properties(
                 self.args.url.as_ref().unwrap().clone(),
@@ -432,14 +1263,14 @@ impl AppRmigCli {
 
             let datasources: DatasourcesProperties = serde_yaml::from_str(yaml.as_str())
                 .map_err(|e| Error::ParseError(path.to_owned(), e.to_string()))?;
-            Ok(datasources.datasources)
+            Ok(datasources.merged())
         }
 
-        // All datasources unwrap and raise error (panic)
+        // Invalid properties of any datasource are returned as error.
         fn create_datasource(
             mut properties: Vec<DatasourceProperties>,
             props: Option<HashMap<String, String>>,
-        ) -> Vec<Box<dyn Driver>> {
+        ) -> anyhow::Result<Vec<Box<dyn Driver>>, Error> {
             properties
                 .iter_mut()
                 .map(|p| {
@@ -454,13 +1285,13 @@ impl AppRmigCli {
                             p.properties.insert(props.clone().unwrap());
                         }
                     }
-                    DatasourceFactory::new(p).unwrap()
+                    DatasourceFactory::new(p)
                 })
-                .collect::<Vec<Box<dyn Driver>>>()
+                .collect::<anyhow::Result<Vec<Box<dyn Driver>>, Error>>()
         }
 
-        self.datasources = create_datasource(datasources, self.args.properties.clone());
-        self
+        self.datasources = create_datasource(datasources, self.args.properties.clone())?;
+        Ok(self)
     }
 }
 
@@ -474,3 +1305,204 @@ impl Default for AppRmigCli {
         }
     }
 }
+
+/// Run [action] with applied migrations under lock of datasource, lock is released on any result.
+/// Core table is upgraded before it is read. Missing core table is created with [create], otherwise
+/// it is read as empty.
+async fn with_locked_core_table<F, R>(
+    driver: &Box<dyn Driver>,
+    create: bool,
+    action: F,
+) -> RmigEmptyResult
+where
+    F: FnOnce(Vec<AppliedMigration>) -> R,
+    R: Future<Output = RmigEmptyResult>,
+{
+    driver.lock().await?;
+    let result = async {
+        driver.validate_connection().await?;
+        let applied = match driver.check_rmig_core_table().await {
+            Ok(_) => {
+                driver.upgrade_rmig_core_table().await?;
+                driver.find_all_in_core_table().await?
+            }
+            Err(Error::RowError(_)) if create => {
+                driver.create_rmig_core_table().await?;
+                vec![]
+            }
+            Err(Error::RowError(_)) => vec![],
+            Err(e) => return Err(e),
+        };
+        action(applied).await
+    }
+    .await;
+    let unlock = driver.unlock().await;
+    result.and(unlock)
+}
+
+/// Core table of previous version of rmig is read without upgrade by commands, which do not change
+/// datasource. It is upgraded by the next command, which changes datasource.
+async fn report_outdated_core_table(driver: &Box<dyn Driver>) -> RmigEmptyResult {
+    if !driver.upgrade_rmig_core_table_sql().await?.is_empty() {
+        println!(
+            "Core table of datasource {} is outdated, it is upgraded by the next run.\n",
+            driver.get_name()
+        );
+    }
+    Ok(())
+}
+
+/// Ask user on stdin, only [y] or [yes] applies changes of repair and rehash commands.
+fn confirm(datasource: &str) -> anyhow::Result<bool, Error> {
+    print!("Apply changes to datasource {}? [y/N] ", datasource);
+    std::io::stdout()
+        .flush()
+        .map_err(|e| Error::IOError(e.to_string()))?;
+    let mut answer = String::new();
+    std::io::stdin()
+        .read_line(&mut answer)
+        .map_err(|e| Error::IOError(e.to_string()))?;
+    let answer = answer.trim().to_lowercase();
+    if answer != "y" && answer != "yes" {
+        warn!("Changes of datasource {} are not applied.", datasource);
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod local_test {
+    use crate::changelogs::Query;
+    use crate::cli::{with_locked_core_table, AppRmigCli, CliArgs, CliReader};
+    use crate::configuration_properties::DatasourceProperties;
+    use crate::driver::{DatasourceFactory, Driver, RmigEmptyResult, LOCK_TIMEOUT};
+    use crate::error::Error;
+    use crate::test_utils::TempDir;
+    use clap::ArgMatches;
+    use std::collections::HashMap;
+
+    fn sqlite(url: &str) -> anyhow::Result<Box<dyn Driver>, Error> {
+        let mut env = HashMap::new();
+        env.insert(LOCK_TIMEOUT.to_string(), "1".to_string());
+        DatasourceFactory::new(&DatasourceProperties::new(None, url.to_string(), Some(env)))
+    }
+
+    fn app(args: CliArgs, datasources: Vec<Box<dyn Driver>>) -> AppRmigCli {
+        AppRmigCli {
+            args: args.clone(),
+            arg_processor: CliReader {
+                args,
+                args_match: ArgMatches::default(),
+            },
+            logging_handler: None,
+            datasources,
+        }
+    }
+
+    /// Changelog [init] of all files in [dir/init].
+    fn changelog(dir: &TempDir) -> String {
+        dir.write(
+            "changelog.yml",
+            &*format!(
+                "properties: {{}}\nchangelogs:\n  - name: init\n    directory: {}/init/*\n",
+                dir.path.display()
+            ),
+        )
+        .unwrap()
+        .display()
+        .to_string()
+    }
+
+    #[tokio::test]
+    pub async fn fail_fast() -> RmigEmptyResult {
+        let dir = TempDir::new("rmig_fail_fast").unwrap();
+        dir.write("init/1.create.sql", "create table a(id int);")
+            .unwrap();
+        let config = changelog(&dir);
+        let urls = ["first", "second"]
+            .iter()
+            .map(|name| format!("sqlite://{}/{}.db", dir.path.display(), name))
+            .collect::<Vec<String>>();
+        // Migration of the first datasource fails, table already exists.
+        let first = sqlite(&*urls[0])?;
+        let query = Query {
+            query: "create table a(id int)".to_string(),
+            opts: Default::default(),
+        };
+        first
+            .migrate_without_transaction(vec![&query].into_iter().collect())
+            .await?;
+
+        let mut args = CliArgs::default();
+        args.config = Some(config);
+        args.parallel = Some(1);
+        args.fail_fast = true;
+        let mut app = app(args, vec![first, sqlite(&*urls[1])?]);
+
+        let report = app.run().await?;
+        assert_eq!(2, app.datasources.len());
+        assert!(report.datasources[0].migrations[0].outcome.is_failed());
+        assert_eq!(
+            Some("Aborted by --fail-fast, datasource is not migrated.".to_string()),
+            report.datasources[1].error
+        );
+        // Lock of failed datasource is released.
+        sqlite(&*urls[0])?.lock().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn run_on_change() -> RmigEmptyResult {
+        let dir = TempDir::new("rmig_run_on_change").unwrap();
+        let view = "--rmig--{\"run_on_change\": true}\ncreate view if not exists v as select 1;";
+        dir.write("init/1.view.sql", view).unwrap();
+        let url = format!("sqlite://{}/rmig.db", dir.path.display());
+        let mut args = CliArgs::default();
+        args.config = Some(changelog(&dir));
+        args.out = Some(format!("{}/script.sql", dir.path.display()));
+        let mut app = app(args, vec![sqlite(&*url)?]);
+
+        app.run().await?;
+        dir.write("init/1.view.sql", &*format!("{}\n-- changed", view))
+            .unwrap();
+        app.script().await?;
+        let script = std::fs::read_to_string(dir.path.join("script.sql")).unwrap();
+        assert!(script.contains("-- Migration: "));
+        assert!(!script.contains("WARNING"));
+
+        // Migration is executed again and its row is replaced.
+        let report = app.run().await?;
+        assert_eq!(
+            "applied",
+            report.datasources[0].migrations[0].outcome.label()
+        );
+        let applied = app.datasources[0].find_all_in_core_table().await?;
+        assert_eq!(1, applied.len());
+        let report = app.run().await?;
+        assert_eq!(
+            "skipped",
+            report.datasources[0].migrations[0].outcome.label()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn unlock_on_error() -> RmigEmptyResult {
+        let properties = DatasourceProperties::new(None, "sqlite::memory:".to_string(), None);
+        let driver = DatasourceFactory::new(&properties)?;
+
+        let result = with_locked_core_table(&driver, false, |applied| async move {
+            assert!(applied.is_empty());
+            Err(Error::RollbackError(
+                "Migration 1 is not applied.".to_string(),
+            ))
+        })
+        .await;
+        assert!(matches!(result, Err(Error::RollbackError(_))));
+
+        // Core table is created and lock is acquired again.
+        with_locked_core_table(&driver, true, |_applied| async { Ok(()) }).await?;
+        driver.check_rmig_core_table().await?;
+        Ok(())
+    }
+}
@@ -3,9 +3,30 @@ use std::collections::HashMap;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DatasourcesProperties {
+    /// Properties shared by all datasources, datasource properties take precedence.
+    #[serde(default)]
+    pub properties: Option<HashMap<String, String>>,
     pub datasources: Vec<DatasourceProperties>,
 }
 
+impl DatasourcesProperties {
+    /// Datasources with shared properties merged into own properties of each datasource.
+    pub fn merged(self) -> Vec<DatasourceProperties> {
+        let shared = self.properties.unwrap_or_default();
+        self.datasources
+            .into_iter()
+            .map(|mut d| {
+                let mut properties = shared.clone();
+                properties.extend(d.properties.take().unwrap_or_default());
+                if !properties.is_empty() {
+                    d.properties = Some(properties);
+                }
+                d
+            })
+            .collect()
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct DatasourceProperties {
     pub name: Option<String>,
@@ -1,8 +1,13 @@
-use crate::changelogs::{Migration, Query};
+use crate::changelogs::{Migration, Query, TransactionMode};
 use crate::configuration_properties::DatasourceProperties;
 use crate::error::Error;
+use crate::history::HistorySource;
+use crate::naming::Version;
+use crate::tera_manager::TeraManager;
 use async_trait::async_trait;
 use std::collections::{HashMap, VecDeque};
+use std::str::FromStr;
+use std::time::Duration;
 
 #[cfg(feature = "mysql")]
 pub mod mysql;
@@ -10,34 +15,234 @@ pub mod mysql;
 pub mod oracle;
 #[cfg(feature = "postgres")]
 pub mod postgres;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
 
 use crate::enum_str;
 
 pub type RmigEmptyResult = anyhow::Result<(), Error>;
 
+/// Property of datasource, changelog or env [--env/-e] with name of core table.
+pub const HISTORY_TABLE: &str = "history_table";
+
+/// Property of env [--env/-e] or datasource, seconds to wait for lock of datasource without
+/// session locks (SQLite), lock of crashed run is reported after this time.
+pub const LOCK_TIMEOUT: &str = "lock_timeout";
+
+/// Seconds to wait for lock without property [lock_timeout].
+pub const DEFAULT_LOCK_TIMEOUT: u64 = 300;
+
+/// Name of core table without property [history_table].
+pub const DEFAULT_HISTORY_TABLE: &str = "CHANGELOGS";
+
+/// Version of rmig, written to column RMIG_VERSION of core table.
+pub const RMIG_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Upgrade of core table schema, applied once in order of version by [Driver::upgrade_rmig_core_table].
+/// Version 1 is the core table of the first release: FILENAME, ORDER_ID, HASH and DATEEXEC.
+/// Applied versions are written to version table, name of core table with suffix [_VERSION].
+pub struct CoreUpgrade {
+    pub version: i32,
+    /// Column added by upgrade. Upgrade with existing column is not applied again, only its version
+    /// is written, so upgrade interrupted before writing of version can be repeated.
+    pub column: &'static str,
+    /// Template of upgrade script with [SCHEMA_ADMIN] and [HISTORY_TABLE], like init script.
+    pub script: &'static str,
+}
+
+/// Version of core table created by init script, it already contains all upgrades.
+fn latest_core_version(upgrades: &[CoreUpgrade]) -> i32 {
+    upgrades.last().map_or(1, |u| u.version)
+}
+
+/// Version of core table without written version, detected by [columns] of core table.
+fn detect_core_version(upgrades: &[CoreUpgrade], columns: &[String]) -> i32 {
+    upgrades
+        .iter()
+        .take_while(|u| has_core_column(u, columns))
+        .last()
+        .map_or(1, |u| u.version)
+}
+
+/// Column of upgrade is found in [columns] of core table, names are compared in uppercase.
+fn has_core_column(upgrade: &CoreUpgrade, columns: &[String]) -> bool {
+    columns
+        .iter()
+        .any(|c| c.to_uppercase() == upgrade.column.to_uppercase())
+}
+
+/// Filter of applied rows of core table. Core table of version 1 has no column STATUS, it is read
+/// without upgrade by commands, which do not change datasource, all its rows are applied.
+fn applied_filter(columns: &[String]) -> &'static str {
+    if columns.iter().any(|c| c.eq_ignore_ascii_case("STATUS")) {
+        " WHERE STATUS = 'SUCCESS'"
+    } else {
+        ""
+    }
+}
+
+/// Select of [Driver::find_all_in_core_table]: FILENAME, ORDER_ID, HASH and VERSION of applied rows.
+/// Rows are sorted by [order_by] and VERSION, so migrations with the same DATEEXEC and ORDER_ID keep
+/// order of versions. Core table before version 3 has no column VERSION, NULL is selected.
+fn select_applied_sql(
+    core_table: &str,
+    columns: &[String],
+    order_id: &str,
+    order_by: &str,
+) -> String {
+    let (version, order_by_version) = if columns.iter().any(|c| c.eq_ignore_ascii_case("VERSION")) {
+        ("VERSION", ", VERSION")
+    } else {
+        ("NULL", "")
+    };
+    format!(
+        "SELECT FILENAME, {}, HASH, {} FROM {}{} ORDER BY {}{}",
+        order_id,
+        version,
+        core_table,
+        applied_filter(columns),
+        order_by,
+        order_by_version
+    )
+}
+
+/// Statements of [Driver::upgrade_rmig_core_table] for core table with [written] version and [columns].
+/// Version table is created by [version_table], if version is not written yet. Every template is
+/// rendered by [render].
+fn core_upgrade_sql<F>(
+    upgrades: &[CoreUpgrade],
+    written: Option<i32>,
+    columns: &[String],
+    core_table: &str,
+    version_table: &str,
+    render: F,
+) -> anyhow::Result<Vec<String>, Error>
+where
+    F: Fn(&str) -> anyhow::Result<String, Error>,
+{
+    let statement = |sql: &str| format!("{};", sql.trim().trim_end_matches(';'));
+    let insert =
+        |version: i32| statement(&insert_core_version_sql(core_table, &version.to_string()));
+    let mut lines = Vec::<String>::new();
+    let version = match written {
+        Some(version) => version,
+        None => {
+            let version = detect_core_version(upgrades, columns);
+            lines.push(statement(&render(version_table)?));
+            lines.push(insert(version));
+            version
+        }
+    };
+    for upgrade in upgrades.iter().filter(|u| u.version > version) {
+        if !has_core_column(upgrade, columns) {
+            lines.push(statement(&render(upgrade.script)?));
+        }
+        lines.push(insert(upgrade.version));
+    }
+    Ok(lines)
+}
+
+/// Result of migration execution, written to columns EXEC_TIME_MS and STATUS of core table.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Execution {
+    pub duration: Duration,
+    pub success: bool,
+}
+
+impl Execution {
+    pub fn success(duration: Duration) -> Self {
+        Execution {
+            duration,
+            success: true,
+        }
+    }
+
+    pub fn failed(duration: Duration) -> Self {
+        Execution {
+            duration,
+            success: false,
+        }
+    }
+
+    pub fn status(&self) -> &'static str {
+        if self.success {
+            "SUCCESS"
+        } else {
+            "FAILED"
+        }
+    }
+
+    pub fn duration_ms(&self) -> i64 {
+        self.duration.as_millis() as i64
+    }
+}
+
+/// Row of core table ([history_table], CHANGELOGS by default).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AppliedMigration {
+    /// Migration name (file path)
+    pub name: String,
+    /// Execution order
+    pub order: i64,
+    /// Hash MD5
+    pub hash: String,
+    /// Full version, [None] for repeatable migration and rows written before core table version 3.
+    pub version: Option<Version>,
+}
+
+/// Database driver. All operations are async and awaited on caller runtime (tokio).
 #[async_trait]
-pub trait Driver {
+pub trait Driver: Send + Sync {
     /// Validation connection per 5 request's.
-    fn validate_connection(&self) -> RmigEmptyResult;
+    async fn validate_connection(&self) -> RmigEmptyResult;
 
-    /// Run migration query, including parameters
-    fn migrate(&self, query: VecDeque<&Query>) -> RmigEmptyResult;
+    /// Run queries of migrations and write their rows of core table in one transaction.
+    /// Migration is never applied without row, row of repeatable migration is written again.
+    /// One migration for [TransactionMode::PerMigration], all pending for [TransactionMode::PerChangelog].
+    async fn migrate(&self, migrations: Vec<&Migration>) -> RmigEmptyResult;
+
+    /// Run undo queries of migration and delete its row of core table in one transaction.
+    async fn migrate_down(&self, migration: &Migration) -> RmigEmptyResult;
+
+    /// Run queries one by one without transaction (autocommit), see [TransactionMode::Autocommit].
+    /// Row of core table is written separately by [add_new_migration].
+    async fn migrate_without_transaction(&self, query: VecDeque<&Query>) -> RmigEmptyResult;
 
     /// Find row in core table, if exists return empty OK
     /// If row is not found, or found but hash is changed, return Err [RowError]
-    fn find_in_core_table(&self, name: String, hash: String) -> RmigEmptyResult;
+    async fn find_in_core_table(&self, name: String, hash: String) -> RmigEmptyResult;
+
+    /// Find all rows in core table, ordered by execution date. Rows of one transaction share the date,
+    /// they are ordered by insertion (sqlite) or by ORDER_ID.
+    async fn find_all_in_core_table(&self) -> anyhow::Result<Vec<AppliedMigration>, Error>;
+
+    /// Scripts of history table of other migration tool, in order of execution. Used by import of history.
+    async fn find_in_history_table(
+        &self,
+        source: &HistorySource,
+    ) -> anyhow::Result<Vec<String>, Error>;
 
     /// Find rmig table. If core table exists, return OK(), if core table does not exists, return Err()
-    fn check_rmig_core_table(&self) -> RmigEmptyResult;
+    async fn check_rmig_core_table(&self) -> RmigEmptyResult;
 
-    /// Create rmig table for changelogs.
-    fn create_rmig_core_table(&self) -> RmigEmptyResult;
+    /// Create rmig table for changelogs and mark it with the latest version of core table.
+    async fn create_rmig_core_table(&self) -> RmigEmptyResult;
+
+    /// Apply upgrades of core table, which are not written to version table yet.
+    /// Core table created without version table is version 1 or 2, it is detected by columns.
+    /// Errors of database are returned, version table is checked in catalog of database.
+    /// Must be called under [lock], core table already exists.
+    async fn upgrade_rmig_core_table(&self) -> RmigEmptyResult;
+
+    /// Statements of [upgrade_rmig_core_table] for existing core table, nothing is executed.
+    /// Empty, if core table is up to date. Used by commands, which do not change datasource.
+    async fn upgrade_rmig_core_table_sql(&self) -> anyhow::Result<Vec<String>, Error>;
 
     /// Getting driver name or hostname, or name.
     fn get_name(&self) -> &str;
 
-    /// Close connection on destroy ref
-    fn close(&self);
+    /// Close connection pool
+    async fn close(&self);
 
     /// Locking current DB for migration
     /// 1. Try lock, if lock acquired = true, loop until acquired = false
@@ -48,11 +253,33 @@ pub trait Driver {
     /// Delete row.
     async fn unlock(&self) -> RmigEmptyResult;
 
-    async fn add_new_migration(&self, migration: Migration) -> RmigEmptyResult;
+    /// Write row of core table with result of execution.
+    /// Rows of previous failed executions of migration are deleted.
+    async fn add_new_migration(
+        &self,
+        migration: Migration,
+        execution: Execution,
+    ) -> RmigEmptyResult;
+
+    /// Delete row of core table by migration name, used by rollback.
+    async fn delete_from_core_table(&self, name: String) -> RmigEmptyResult;
+
+    /// Update hash of migration in core table, used by migrations with [run_on_change].
+    async fn update_hash_in_core_table(&self, migration: Migration) -> RmigEmptyResult;
+
+    /// Script of core table, executed by [create_rmig_core_table] or written to standalone script.
+    fn rmig_core_table_sql(&self) -> anyhow::Result<String, Error>;
+
+    /// Migration as standalone script: queries and row of core table in one transaction of dialect.
+    /// Row is the same as [add_new_migration] writes, so the script applied by hand is recognised by rmig.
+    fn script_migration(&self, migration: &Migration) -> String;
 }
 
-pub trait DriverFactory<T: Clone + Drop + Driver + Sized> {
-    fn new(props: &DatasourceProperties) -> T;
+/// Creating driver without connecting to database.
+/// Connection is checked by [Driver::validate_connection].
+pub trait DriverFactory<T: Clone + Driver + Sized> {
+    /// Driver by properties of datasource, invalid properties are returned as error.
+    fn new(props: &DatasourceProperties) -> anyhow::Result<T, Error>;
 }
 
 /// Factory component for creating Datasource's and methods for migration.
@@ -70,18 +297,26 @@ impl DatasourceFactory {
         if url.trim().starts_with("postgres") {
             return Ok(Box::new(crate::driver::postgres::DatasourcePostgres::new(
                 props,
-            )));
+            )?));
         }
 
         #[cfg(feature = "ora")]
         if url.trim().starts_with("oracle") {
-            // return Ok(Box::new(crate::driver::oracle::DatasourceOracle::new(props)));
-            panic!("Oracle driver no impl.");
+            return Ok(Box::new(crate::driver::oracle::DatasourceOracle::new(
+                props,
+            )?));
         }
 
         #[cfg(feature = "mysql")]
         if url.trim().starts_with("mysql") {
-            panic!("Mysql driver no impl.");
+            return Ok(Box::new(crate::driver::mysql::DatasourceMysql::new(props)?));
+        }
+
+        #[cfg(feature = "sqlite")]
+        if url.trim().starts_with("sqlite") {
+            return Ok(Box::new(crate::driver::sqlite::DatasourceSqlite::new(
+                props,
+            )?));
         }
 
         let _url = url::Url::parse(&*url)
@@ -101,6 +336,127 @@ fn generate_lock(db_name: String) -> i64 {
     x.finalize() as i64
 }
 
+/// Quoted sql string literal.
+fn sql_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+/// Statement with terminator [;].
+fn terminate_statement(statement: &str) -> String {
+    let statement = statement.trim();
+    if statement.ends_with(';') {
+        statement.to_string()
+    } else {
+        format!("{};", statement)
+    }
+}
+
+/// Name of machine running rmig, written to column CLIENT_HOST of core table.
+fn client_host() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_e| std::env::var("COMPUTERNAME"))
+        .unwrap_or_else(|_e| "unknown".to_string())
+}
+
+/// Insert of core table row with bind parameters of dialect, [param] returns placeholder by position.
+/// Parameters: FILENAME, ORDER_ID, HASH, STAGE, AUTHOR, EXEC_TIME_MS, STATUS, CLIENT_HOST, RMIG_VERSION,
+/// VERSION. [executed_by] is expression of current database user.
+fn add_new_migration_insert(
+    core_table: &str,
+    param: fn(usize) -> String,
+    executed_by: &str,
+) -> String {
+    let params = (1..=10).map(param).collect::<Vec<String>>();
+    format!(
+        "INSERT INTO {}(FILENAME, ORDER_ID, HASH, STAGE, AUTHOR, EXEC_TIME_MS, STATUS, CLIENT_HOST, RMIG_VERSION, VERSION, EXECUTED_BY) VALUES ({}, {})",
+        core_table,
+        params.join(", "),
+        executed_by
+    )
+}
+
+/// Delete of previous execution of migration, core table keeps single row per FILENAME.
+/// [param] is placeholder of FILENAME.
+fn delete_migration_sql(core_table: &str, param: &str) -> String {
+    format!("DELETE FROM {} WHERE FILENAME = {}", core_table, param)
+}
+
+/// Applied versions of core table, the latest first. Fails, if version table does not exist.
+fn select_core_version_sql(core_table: &str) -> String {
+    format!(
+        "SELECT VERSION FROM {}_VERSION ORDER BY VERSION DESC",
+        core_table
+    )
+}
+
+/// Insert of applied version of core table, [param] is placeholder of VERSION.
+fn insert_core_version_sql(core_table: &str, param: &str) -> String {
+    format!(
+        "INSERT INTO {}_VERSION(VERSION) VALUES ({})",
+        core_table, param
+    )
+}
+
+/// Script of core table from template, rendered with [SCHEMA_ADMIN] and [HISTORY_TABLE].
+fn core_table_sql(
+    schema_admin: &str,
+    history_table: &str,
+    template: &str,
+) -> anyhow::Result<String, Error> {
+    let mut map = HashMap::<String, String>::new();
+    map.insert("HISTORY_TABLE".to_string(), history_table.to_string());
+    if schema_admin.ne("") {
+        map.insert("SCHEMA_ADMIN".to_string(), schema_admin.to_string());
+    }
+    TeraManager::new(map).apply("core.sql", template)
+}
+
+/// Row of core table with literal values, used by standalone script. Previous row of migration
+/// is replaced. Execution time and client host are unknown, when the script is applied by hand.
+fn add_new_migration_sql(core_table: &str, migration: &Migration, executed_by: &str) -> String {
+    let optional = |value: &Option<String>| {
+        value
+            .as_ref()
+            .map_or("NULL".to_string(), |v| sql_literal(&*v))
+    };
+    let name = sql_literal(&*migration.name);
+    format!(
+        "{};\nINSERT INTO {}(FILENAME, ORDER_ID, HASH, STAGE, AUTHOR, STATUS, RMIG_VERSION, VERSION, EXECUTED_BY) VALUES ({}, {}, {}, {}, {}, 'SUCCESS', {}, {}, {});",
+        delete_migration_sql(core_table, &*name),
+        core_table,
+        name,
+        migration.order,
+        sql_literal(&*migration.hash),
+        optional(&migration.stage),
+        optional(&migration.author),
+        sql_literal(RMIG_VERSION),
+        optional(&migration.version_key()),
+        executed_by
+    )
+}
+
+/// Queries of migration and row of core table between [begin] and [commit] statements.
+/// Migration with [TransactionMode::Autocommit] is written without transaction.
+fn script_transaction(begin: &str, migration: &Migration, insert: String, commit: &str) -> String {
+    let autocommit = migration.transaction() == TransactionMode::Autocommit;
+    let mut lines = Vec::<String>::new();
+    if !autocommit {
+        lines.push(begin.to_string());
+    }
+    lines.extend(
+        migration
+            .query_list
+            .iter()
+            .filter(|q| !q.query.trim().is_empty())
+            .map(|q| terminate_statement(&*q.query)),
+    );
+    lines.push(insert);
+    if !autocommit {
+        lines.push(commit.to_string());
+    }
+    lines.join("\n")
+}
+
 struct DatasourceWrapper {
     properties: Box<DatasourceProperties>,
 }
@@ -150,6 +506,59 @@ impl DatasourceWrapper {
         }
         separator.to_owned()
     }
+
+    /// Name of core table, property [history_table]. Name is written into queries as is,
+    /// so only letters, digits and [_] are allowed.
+    pub fn get_history_table(&self) -> anyhow::Result<String, Error> {
+        let history_table = self
+            .properties
+            .properties
+            .as_ref()
+            .and_then(|p| p.get(HISTORY_TABLE))
+            .map(|t| t.trim().to_string())
+            .unwrap_or_else(|| DEFAULT_HISTORY_TABLE.to_string());
+        if history_table.is_empty()
+            || !history_table
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(Error::ParseError(
+                HISTORY_TABLE.to_string(),
+                format!(
+                    "Name '{}' is not valid. Please use letters, digits and _.",
+                    history_table
+                ),
+            ));
+        }
+        Ok(history_table)
+    }
+
+    /// Time to wait for lock, property [lock_timeout] in seconds.
+    pub fn get_lock_timeout(&self) -> anyhow::Result<Duration, Error> {
+        self.properties
+            .properties
+            .as_ref()
+            .and_then(|p| p.get(LOCK_TIMEOUT))
+            .map_or(Ok(DEFAULT_LOCK_TIMEOUT), |t| {
+                u64::from_str(t.trim()).map_err(|_e| {
+                    Error::ParseError(
+                        LOCK_TIMEOUT.to_string(),
+                        format!("Value '{}' is not number of seconds.", t),
+                    )
+                })
+            })
+            .map(Duration::from_secs)
+    }
+
+    /// Core table with admin schema: [SCHEMA_ADMIN.history_table].
+    pub fn get_core_table(&self, history_table: &str) -> String {
+        format!(
+            "{}{}{}",
+            self.get_schema_admin(),
+            self.get_separator(),
+            history_table
+        )
+    }
 }
 
 enum_str! {
@@ -166,7 +575,118 @@ enum_str! {
 
 #[cfg(test)]
 mod test_local {
-    use crate::driver::DriverOptions;
+    use crate::configuration_properties::DatasourceProperties;
+    use crate::driver::{
+        add_new_migration_insert, add_new_migration_sql, detect_core_version, script_transaction,
+        select_applied_sql, CoreUpgrade, DatasourceWrapper, DriverOptions, HISTORY_TABLE,
+        RMIG_VERSION,
+    };
+    use crate::error::Error;
+    use crate::test_utils::MigrationBuilder;
+    use std::collections::HashMap;
+
+    fn wrapper(history_table: &str) -> DatasourceWrapper {
+        let mut env = HashMap::new();
+        env.insert(HISTORY_TABLE.to_string(), history_table.to_string());
+        env.insert("SCHEMA_ADMIN".to_string(), "admin".to_string());
+        DatasourceWrapper::new(Box::new(DatasourceProperties::new(
+            None,
+            "sqlite::memory:".to_string(),
+            Some(env),
+        )))
+    }
+
+    #[test]
+    pub fn history_table() -> anyhow::Result<()> {
+        let trimmed = wrapper(" APP_HISTORY ");
+        assert_eq!("APP_HISTORY", trimmed.get_history_table()?);
+        assert_eq!("admin.APP_HISTORY", trimmed.get_core_table("APP_HISTORY"));
+        assert!(matches!(
+            wrapper("APP; DROP TABLE X").get_history_table(),
+            Err(Error::ParseError(_, _))
+        ));
+        assert!(wrapper("").get_history_table().is_err());
+        Ok(())
+    }
+
+    #[test]
+    pub fn detect_version_by_columns() -> anyhow::Result<()> {
+        let upgrades = [
+            CoreUpgrade {
+                version: 2,
+                column: "STATUS",
+                script: "",
+            },
+            CoreUpgrade {
+                version: 3,
+                column: "ID",
+                script: "",
+            },
+        ];
+        let columns = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            1,
+            detect_core_version(&upgrades, &columns(&["FILENAME", "HASH"]))
+        );
+        assert_eq!(
+            2,
+            detect_core_version(&upgrades, &columns(&["FILENAME", "status"]))
+        );
+        assert_eq!(
+            3,
+            detect_core_version(&upgrades, &columns(&["STATUS", "ID"]))
+        );
+        // Upgrades are applied in order, column of later upgrade does not skip earlier one.
+        assert_eq!(1, detect_core_version(&upgrades, &columns(&["ID"])));
+        Ok(())
+    }
+
+    #[test]
+    pub fn script_migration_transaction() -> anyhow::Result<()> {
+        let migration = MigrationBuilder::new("./migration/1.it's.sql")
+            .query("create table a(id int)")
+            .query("insert into a values (1);")
+            .query("  ")
+            .stage("init")
+            .build();
+
+        let insert = add_new_migration_sql("CHANGELOGS", &migration, "CURRENT_USER");
+        assert_eq!(
+            "BEGIN;\n\
+             create table a(id int);\n\
+             insert into a values (1);\n\
+             DELETE FROM CHANGELOGS WHERE FILENAME = './migration/1.it''s.sql';\n\
+             INSERT INTO CHANGELOGS(FILENAME, ORDER_ID, HASH, STAGE, AUTHOR, STATUS, RMIG_VERSION, VERSION, EXECUTED_BY) VALUES ('./migration/1.it''s.sql', 1, 'md5', 'init', NULL, 'SUCCESS', '{}', '00000000000000000001', CURRENT_USER);\n\
+             COMMIT;",
+            script_transaction("BEGIN;", &migration, insert, "COMMIT;")
+                .replace(RMIG_VERSION, "{}")
+        );
+        assert_eq!(
+            "INSERT INTO CHANGELOGS(FILENAME, ORDER_ID, HASH, STAGE, AUTHOR, EXEC_TIME_MS, STATUS, CLIENT_HOST, RMIG_VERSION, VERSION, EXECUTED_BY) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, CURRENT_USER)",
+            add_new_migration_insert("CHANGELOGS", |i| format!("${}", i), "CURRENT_USER")
+        );
+        let columns = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            "SELECT FILENAME, ORDER_ID, HASH, VERSION FROM CHANGELOGS WHERE STATUS = 'SUCCESS' ORDER BY DATEEXEC, ORDER_ID, VERSION",
+            select_applied_sql(
+                "CHANGELOGS",
+                &columns(&["FILENAME", "STATUS", "VERSION"]),
+                "ORDER_ID",
+                "DATEEXEC, ORDER_ID"
+            )
+        );
+        // Outdated core table is read by commands, which do not upgrade it.
+        assert_eq!(
+            "SELECT FILENAME, ORDER_ID, HASH, NULL FROM CHANGELOGS ORDER BY DATEEXEC, ORDER_ID",
+            select_applied_sql(
+                "CHANGELOGS",
+                &columns(&["FILENAME"]),
+                "ORDER_ID",
+                "DATEEXEC, ORDER_ID"
+            )
+        );
+        Ok(())
+    }
 
     #[test]
     pub fn parameter_name_eq() -> anyhow::Result<()> {
@@ -182,4 +702,4 @@ mod test_local {
         );
         Ok(())
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,1096 @@
+use crate::changelogs::{Migration, Query};
+use crate::configuration_properties::DatasourceProperties;
+use crate::driver::{
+    add_new_migration_insert, add_new_migration_sql, client_host, core_table_sql, core_upgrade_sql,
+    delete_migration_sql, detect_core_version, has_core_column, insert_core_version_sql,
+    latest_core_version, script_transaction, select_applied_sql, select_core_version_sql,
+    AppliedMigration, CoreUpgrade, DatasourceWrapper, Driver, DriverFactory, Execution,
+    RmigEmptyResult, RMIG_VERSION,
+};
+use crate::error::Error;
+use crate::history::HistorySource;
+use crate::naming::Version;
+use async_trait::async_trait;
+use log::{debug, info, warn};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions, SqliteRow};
+use sqlx::{Row, SqlitePool};
+use std::borrow::Borrow;
+use std::collections::VecDeque;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug)]
+pub struct DatasourceSqlite {
+    pub name: String,
+    pub pool: Box<SqlitePool>,
+    pub schema_admin: String,
+    pub separator: String,
+    /// Name of core table, property [history_table].
+    pub history_table: String,
+    /// Core table with admin schema.
+    pub core_table: String,
+    /// Lock row is inserted by current datasource.
+    locked: Arc<AtomicBool>,
+    /// Time to wait for lock row of another process, property [lock_timeout].
+    lock_timeout: Duration,
+}
+
+/// Creating datasource
+/// Pool is lazy, connection is opened on first query.
+/// Url examples: sqlite://rmig.db, sqlite::memory:
+impl DriverFactory<DatasourceSqlite> for DatasourceSqlite {
+    fn new(props: &DatasourceProperties) -> anyhow::Result<DatasourceSqlite, Error> {
+        let wrapper = DatasourceWrapper::new(Box::new(props.to_owned()));
+        let history_table = wrapper.get_history_table()?;
+        let lock_timeout = wrapper.get_lock_timeout()?;
+        let url = wrapper.get_url();
+        // File url does not contains host, use datasource name or url.
+        let name = props.name.clone().unwrap_or(url.to_string());
+
+        let conn_opts = SqliteConnectOptions::from_str(url)
+            .map_err(|e| { Error::CreatingDatasourceError(format!("Url is not valid. Check your configuration and url parameters. Datasoruce name: {}\nError: {:?}", &name, e)) })?
+            .create_if_missing(true);
+
+        // Every connection to in-memory database opens new empty database.
+        let pool_opts = if url.contains(":memory:") || url.contains("mode=memory") {
+            SqlitePoolOptions::new()
+                .max_connections(1)
+                .idle_timeout(None::<Duration>)
+                .max_lifetime(None::<Duration>)
+        } else {
+            SqlitePoolOptions::new()
+        };
+
+        let pool = Box::new(pool_opts.connect_lazy_with(conn_opts));
+
+        Ok(DatasourceSqlite {
+            name,
+            pool,
+            schema_admin: wrapper.get_schema_admin(),
+            separator: wrapper.get_separator(),
+            core_table: wrapper.get_core_table(&*history_table),
+            history_table,
+            locked: Arc::new(AtomicBool::new(false)),
+            lock_timeout,
+        })
+    }
+}
+
+/// Upgrades of core table in order of version.
+const CORE_UPGRADES: &[CoreUpgrade] = &[
+    CoreUpgrade {
+        version: 2,
+        column: "STATUS",
+        script: include_str!("../init/sqlite_upgrade_2.sql"),
+    },
+    CoreUpgrade {
+        version: 3,
+        column: "VERSION",
+        script: include_str!("../init/sqlite_upgrade_3.sql"),
+    },
+];
+
+impl DatasourceSqlite {
+    /// Table exists in admin schema, name is compared in uppercase.
+    async fn exists_table(&self, table: &str) -> anyhow::Result<bool, Error> {
+        // language=SQL
+        sqlx::query_scalar(format!("SELECT EXISTS(SELECT 1 FROM {}{}sqlite_master WHERE type = 'table' AND UPPER(name) = '{}') as ex", &*self.schema_admin, &*self.separator, table.to_uppercase()).as_str())
+            // language=RUST
+            .fetch_one(&*self.pool)
+            .await
+            .map_err(|e| Error::SQLError(format!("{:?}", e)))
+    }
+
+    /// Columns of core table in uppercase.
+    async fn core_columns(&self) -> anyhow::Result<Vec<String>, Error> {
+        let schema = if self.schema_admin.ne("") {
+            format!(", '{}'", &*self.schema_admin)
+        } else {
+            "".to_string()
+        };
+        // language=SQL
+        sqlx::query_scalar(
+            format!(
+                "SELECT UPPER(name) FROM pragma_table_info('{}'{})",
+                &*self.history_table, schema
+            )
+            .as_str(),
+        )
+        // language=RUST
+        .fetch_all(&*self.pool)
+        .await
+        .map_err(|e| Error::SQLError(format!("{:?}", e)))
+    }
+
+    /// Lock row of another process is not deleted in [lock_timeout].
+    async fn lock_holder_error(&self) -> Error {
+        // language=SQL
+        let holder: Result<(Option<String>, String), sqlx::Error> = sqlx::query_as(
+            format!(
+                "SELECT HOLDER, CAST(LOCKED_AT AS TEXT) FROM {}{}RMIG_LOCK WHERE ID = 1",
+                &*self.schema_admin, &*self.separator
+            )
+            .as_str(),
+        )
+        .fetch_one(self.pool.borrow())
+        // language=RUST
+        .await;
+        let holder = match holder {
+            Ok((holder, locked_at)) => format!(
+                "Lock row is inserted by {} at {}",
+                holder.unwrap_or_else(|| "unknown".to_string()),
+                locked_at
+            ),
+            Err(e) => format!("Lock row is not readable: {:?}", e),
+        };
+        Error::LockError(format!(
+            "Datasource {} is still locked after {:?}. {}. Delete the row from RMIG_LOCK, if the process is not running.",
+            &self.name, self.lock_timeout, holder
+        ))
+    }
+
+    /// The latest version of version table, [None] if version table does not exist or is empty.
+    async fn written_core_version(&self) -> anyhow::Result<Option<i32>, Error> {
+        if !self
+            .exists_table(&*format!("{}_VERSION", &*self.history_table))
+            .await?
+        {
+            return Ok(None);
+        }
+        let select = select_core_version_sql(&*self.core_table);
+        // language=SQL
+        let versions: Vec<i32> = sqlx::query_scalar(&*select)
+            .fetch_all(&*self.pool)
+            .await
+            // language=RUST
+            .map_err(|e| Error::SQLError(format!("{:?}", e)))?;
+        Ok(versions.first().copied())
+    }
+
+    /// Create version table, if it does not exist, and write applied version of core table.
+    async fn write_core_version(&self, version: i32) -> RmigEmptyResult {
+        let table = core_table_sql(
+            &*self.schema_admin,
+            &*self.history_table,
+            include_str!("../init/sqlite_version.sql"),
+        )?;
+        sqlx::query(&*table)
+            .execute(&*self.pool)
+            .await
+            .map_err(|e| Error::SQLError(format!("{:?}", e)))?;
+        let insert = insert_core_version_sql(&*self.core_table, "?1");
+        sqlx::query(&*insert)
+            .bind(version)
+            .execute(&*self.pool)
+            .await
+            .map_err(|e| Error::SQLError(format!("{:?}", e)))?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Driver for DatasourceSqlite {
+    async fn validate_connection(&self) -> RmigEmptyResult {
+        sqlx::query("SELECT 1")
+            .execute(self.pool.borrow())
+            .await
+            .map_err(|e| {
+                Error::ConnectionValidationError(format!(
+                    "Datasource is not configured or not working. {}\nError: {:?}",
+                    &self.name, e
+                ))
+            })?;
+        info!("Connection pool success creating.\nPing query (select 1) is success.");
+        Ok(())
+    }
+
+    async fn migrate(&self, migrations: Vec<&Migration>) -> RmigEmptyResult {
+        let delete = format!("DELETE FROM {} WHERE FILENAME = ?1;", &*self.core_table);
+        // SQLite does not have database users.
+        let insert = add_new_migration_insert(&*self.core_table, |i| format!("?{}", i), "NULL");
+        let host = client_host();
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| Error::SQLError(format!("{:?}.\nOpen transaction failed.", e)))?;
+        let start = Instant::now();
+
+        for m in migrations {
+            let migration_start = Instant::now();
+            for q in m.query_list.iter() {
+                debug!("Running sql: {}", &*q.query);
+                // Execute, if error, rollback transaction
+                sqlx::query(&*q.query)
+                    .execute(&mut tx)
+                    .await
+                    .map_err(|e| Error::SQLError(format!("{:?}.\nSQL - {}", e, &*q.query)))?;
+            }
+            // Row of repeatable migration is written again.
+            sqlx::query(&*delete)
+                .bind(&*m.name)
+                .execute(&mut tx)
+                .await
+                .map_err(|e| Error::SQLError(format!("{:?}", e)))?;
+            let execution = Execution::success(migration_start.elapsed());
+            sqlx::query(&*insert)
+                .bind(&*m.name)
+                .bind(m.order)
+                .bind(&*m.hash)
+                .bind(m.stage.as_deref())
+                .bind(m.author.as_deref())
+                .bind(execution.duration_ms())
+                .bind(execution.status())
+                .bind(&*host)
+                .bind(RMIG_VERSION)
+                .bind(m.version_key())
+                .execute(&mut tx)
+                .await
+                .map_err(|e| Error::SQLError(format!("{:?}", e)))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| Error::SQLError(format!("{:?}.\nCommit transaction failed.", e)))?;
+        debug!("Success! Time elapsed: {:?}", start.elapsed());
+        Ok(())
+    }
+
+    async fn migrate_down(&self, migration: &Migration) -> RmigEmptyResult {
+        let delete = format!("DELETE FROM {} WHERE FILENAME = ?1;", &*self.core_table);
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| Error::SQLError(format!("{:?}.\nOpen transaction failed.", e)))?;
+
+        for q in migration.down_query_list.iter() {
+            debug!("Running sql: {}", &*q.query);
+            // Execute, if error, rollback transaction
+            sqlx::query(&*q.query)
+                .execute(&mut tx)
+                .await
+                .map_err(|e| Error::SQLError(format!("{:?}.\nSQL - {}", e, &*q.query)))?;
+        }
+        sqlx::query(&*delete)
+            .bind(&*migration.name)
+            .execute(&mut tx)
+            .await
+            .map_err(|e| Error::SQLError(format!("{:?}", e)))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| Error::SQLError(format!("{:?}.\nCommit transaction failed.", e)))?;
+        Ok(())
+    }
+
+    async fn migrate_without_transaction(&self, query: VecDeque<&Query>) -> RmigEmptyResult {
+        let start = Instant::now();
+        for q in query {
+            debug!("Running sql without transaction: {}", &*q.query);
+            sqlx::query(&*q.query)
+                .execute(&*self.pool)
+                .await
+                .map_err(|e| Error::SQLError(format!("{:?}.\nSQL - {}", e, &*q.query)))?;
+        }
+        debug!("Success! Time elapsed: {:?}", start.elapsed());
+        Ok(())
+    }
+
+    async fn find_in_core_table(&self, name: String, hash: String) -> RmigEmptyResult {
+        let table = &self.core_table;
+        let sql = format!("select exists(select 1 from {} where FILENAME=?1 and STATUS='SUCCESS') as erow, exists(select 1 from {} where FILENAME=?1 and HASH=?2 and STATUS='SUCCESS') as erowhash", table, table);
+        // language=SQL
+        let query: SqliteRow = sqlx::query(&*sql)
+            .bind(&name)
+            .bind(&hash)
+            .fetch_one(&*self.pool)
+            .await
+            // language=RUST
+            .map_err(|e| {
+                Error::SQLError(
+                    format!(
+                        "Row with filename {} and hash {} return error.\nError: {:?}",
+                        &name, &hash, e
+                    )
+                    .to_string(),
+                )
+            })?;
+
+        let erow: bool = query.get("erow");
+        // If row exists find row with hash.
+        if !erow {
+            return Err(Error::RowError(
+                format!("Row with filename {} not found.", &name).to_string(),
+            ));
+        }
+
+        let erowhash: bool = query.get("erowhash");
+        // If row with hash and name not found, but row with filename found, hash has been changed.
+        if !erowhash {
+            return Err(Error::HashUniqueError(format!("Row with filename {} and hash {} not found. Hash has been changed. Please revert your changed, or set query option run_on_change.", &name, &hash).to_string()));
+        }
+
+        Ok(())
+    }
+
+    async fn find_all_in_core_table(&self) -> anyhow::Result<Vec<AppliedMigration>, Error> {
+        let columns = self.core_columns().await?;
+        let sql = select_applied_sql(&*self.core_table, &columns, "ORDER_ID", "DATEEXEC, ROWID");
+        // language=SQL
+        let rows: Vec<(String, i64, String, Option<String>)> = sqlx::query_as(&*sql)
+            .fetch_all(&*self.pool)
+            .await
+            // language=RUST
+            .map_err(|e| Error::SQLError(format!("{:?}", e)))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(name, order, hash, version)| AppliedMigration {
+                name,
+                order,
+                hash,
+                version: version.as_deref().and_then(Version::parse),
+            })
+            .collect())
+    }
+
+    async fn find_in_history_table(
+        &self,
+        source: &HistorySource,
+    ) -> anyhow::Result<Vec<String>, Error> {
+        // language=SQL
+        sqlx::query_scalar(&*source.select_sql("", "1"))
+            .fetch_all(&*self.pool)
+            .await
+            // language=RUST
+            .map_err(|e| Error::SQLError(format!("{:?}", e)))
+    }
+
+    async fn check_rmig_core_table(&self) -> RmigEmptyResult {
+        if self.exists_table(&*self.history_table).await? {
+            Ok(())
+        } else {
+            Err(Error::RowError("Not found core table.".to_string()))
+        }
+    }
+
+    async fn create_rmig_core_table(&self) -> RmigEmptyResult {
+        info!("Creating core table.");
+        let table = self.rmig_core_table_sql()?;
+
+        sqlx::query(&*table)
+            .execute(&*self.pool)
+            .await
+            .map_err(|e| Error::SQLError(format!("{:?}", e)))?;
+        self.write_core_version(latest_core_version(CORE_UPGRADES))
+            .await
+    }
+
+    async fn upgrade_rmig_core_table(&self) -> RmigEmptyResult {
+        let columns = self.core_columns().await?;
+        let version = match self.written_core_version().await? {
+            Some(version) => version,
+            None => {
+                // Core table is created without version table.
+                let version = detect_core_version(CORE_UPGRADES, &columns);
+                self.write_core_version(version).await?;
+                version
+            }
+        };
+
+        let insert = insert_core_version_sql(&*self.core_table, "?1");
+        for upgrade in CORE_UPGRADES.iter().filter(|u| u.version > version) {
+            let script = core_table_sql(&*self.schema_admin, &*self.history_table, upgrade.script)?;
+            // Upgrade and its version are written in one transaction.
+            let mut tx = self
+                .pool
+                .begin()
+                .await
+                .map_err(|e| Error::SQLError(format!("{:?}.\nOpen transaction failed.", e)))?;
+            if has_core_column(upgrade, &columns) {
+                info!(
+                    "Core table already contains column {}, version {} is written.",
+                    upgrade.column, upgrade.version
+                );
+            } else {
+                info!("Upgrading core table to version {}.", upgrade.version);
+                sqlx::query(&*script)
+                    .execute(&mut tx)
+                    .await
+                    .map_err(|e| Error::SQLError(format!("{:?}", e)))?;
+            }
+            sqlx::query(&*insert)
+                .bind(upgrade.version)
+                .execute(&mut tx)
+                .await
+                .map_err(|e| Error::SQLError(format!("{:?}", e)))?;
+            tx.commit()
+                .await
+                .map_err(|e| Error::SQLError(format!("{:?}.\nCommit transaction failed.", e)))?;
+        }
+        Ok(())
+    }
+
+    async fn upgrade_rmig_core_table_sql(&self) -> anyhow::Result<Vec<String>, Error> {
+        let columns = self.core_columns().await?;
+        let written = self.written_core_version().await?;
+        core_upgrade_sql(
+            CORE_UPGRADES,
+            written,
+            &columns,
+            &*self.core_table,
+            include_str!("../init/sqlite_version.sql"),
+            |template| core_table_sql(&*self.schema_admin, &*self.history_table, template),
+        )
+    }
+
+    fn get_name(&self) -> &str {
+        self.name.as_str()
+    }
+
+    async fn close(&self) {
+        self.pool.close().await;
+        if self.pool.is_closed() {
+            debug!("Pool success closed.");
+        }
+    }
+
+    /// SQLite does not have session locks, we insert lock row into RMIG_LOCK.
+    /// Row with the same ID can be inserted only once, other process waits until row is deleted.
+    /// Row of crashed process is never deleted, so waiting is stopped after [lock_timeout].
+    async fn lock(&self) -> RmigEmptyResult {
+        let start = Instant::now();
+
+        // language=SQL
+        sqlx::query(
+            format!(
+                "CREATE TABLE IF NOT EXISTS {}{}RMIG_LOCK(ID INTEGER PRIMARY KEY, HOLDER TEXT, LOCKED_AT TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP)",
+                &*self.schema_admin, &*self.separator
+            )
+            .as_str(),
+        )
+        .execute(self.pool.borrow())
+        // language=RUST
+        .await
+        .map_err(|e| Error::SQLError(format!("{:?}", e)))?;
+
+        info!("Starting locking session with lock table RMIG_LOCK.");
+        let holder = format!("{} (pid {})", client_host(), std::process::id());
+        loop {
+            // language=SQL
+            let inserted = sqlx::query(
+                format!(
+                    "INSERT OR IGNORE INTO {}{}RMIG_LOCK(ID, HOLDER) VALUES (1, ?1)",
+                    &*self.schema_admin, &*self.separator
+                )
+                .as_str(),
+            )
+            .bind(&*holder)
+            .execute(self.pool.borrow())
+            // language=RUST
+            .await
+            .map_err(|e| Error::SQLError(format!("{:?}", e)))?
+            .rows_affected();
+
+            if inserted == 1 {
+                break;
+            }
+            if start.elapsed() >= self.lock_timeout {
+                return Err(self.lock_holder_error().await);
+            }
+            debug!("Lock row already exists. Waiting.");
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+        self.locked.store(true, Ordering::SeqCst);
+
+        let elapsed = start.elapsed();
+
+        info!("Success locking session! Time elapsed: {:?}", elapsed);
+
+        Ok(())
+    }
+
+    async fn unlock(&self) -> RmigEmptyResult {
+        // Do not delete lock row of another process.
+        if !self.locked.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        // language=SQL
+        sqlx::query(
+            format!(
+                "DELETE FROM {}{}RMIG_LOCK WHERE ID = 1",
+                &*self.schema_admin, &*self.separator
+            )
+            .as_str(),
+        )
+        .execute(self.pool.borrow())
+        // language=RUST
+        .await
+        .map_err(|e| Error::SQLError(format!("{:?}", e)))?;
+
+        Ok(())
+    }
+
+    async fn add_new_migration(
+        &self,
+        migration: Migration,
+        execution: Execution,
+    ) -> RmigEmptyResult {
+        let delete = delete_migration_sql(&*self.core_table, "?1");
+        // Previous row of migration is replaced in one transaction.
+        let mut tx = self
+            .pool
+            .begin()
+            .await
+            .map_err(|e| Error::SQLError(format!("{:?}.\nOpen transaction failed.", e)))?;
+        sqlx::query(&*delete)
+            .bind(&*migration.name)
+            .execute(&mut tx)
+            .await
+            .map_err(|e| Error::SQLError(format!("{:?}", e)))?;
+        let _sql = add_new_migration_insert(&*self.core_table, |i| format!("?{}", i), "NULL");
+        sqlx::query(&*_sql)
+            .bind(&*migration.name)
+            .bind(migration.order.clone())
+            .bind(&*migration.hash)
+            .bind(migration.stage.as_deref())
+            .bind(migration.author.as_deref())
+            .bind(execution.duration_ms())
+            .bind(execution.status())
+            .bind(client_host())
+            .bind(RMIG_VERSION)
+            .bind(migration.version_key())
+            .execute(&mut tx)
+            .await
+            .map_err(|e| Error::SQLError(format!("{:?}", e)))?;
+        tx.commit()
+            .await
+            .map_err(|e| Error::SQLError(format!("{:?}.\nCommit transaction failed.", e)))?;
+        Ok(())
+    }
+
+    async fn delete_from_core_table(&self, name: String) -> RmigEmptyResult {
+        let _sql = format!("DELETE FROM {} WHERE FILENAME = ?1;", &*self.core_table);
+        sqlx::query(&*_sql)
+            .bind(&*name)
+            .execute(self.pool.borrow())
+            .await
+            .map_err(|e| Error::SQLError(format!("{:?}", e)))?;
+        Ok(())
+    }
+
+    async fn update_hash_in_core_table(&self, migration: Migration) -> RmigEmptyResult {
+        let _sql = format!(
+            "UPDATE {} SET HASH = ?1 WHERE FILENAME = ?2;",
+            &*self.core_table
+        );
+        sqlx::query(&*_sql)
+            .bind(&*migration.hash)
+            .bind(&*migration.name)
+            .execute(self.pool.borrow())
+            .await
+            .map_err(|e| Error::SQLError(format!("{:?}", e)))?;
+        Ok(())
+    }
+
+    fn rmig_core_table_sql(&self) -> anyhow::Result<String, Error> {
+        core_table_sql(
+            &*self.schema_admin,
+            &*self.history_table,
+            include_str!("../init/sqlite_init.sql"),
+        )
+    }
+
+    fn script_migration(&self, migration: &Migration) -> String {
+        let insert = add_new_migration_sql(&*self.core_table, migration, "NULL");
+        script_transaction("BEGIN TRANSACTION;", migration, insert, "COMMIT;")
+    }
+}
+
+impl Drop for DatasourceSqlite {
+    fn drop(&mut self) {
+        // Lock row is not released with connection, it must be deleted by [Driver::unlock].
+        if Arc::strong_count(&self.locked) == 1 && self.locked.load(Ordering::SeqCst) {
+            warn!(
+                "Lock row in RMIG_LOCK is not deleted for datasource {}.",
+                &self.name
+            );
+        }
+    }
+}
+
+// sqlite, does not require external database
+#[cfg(test)]
+mod local_test {
+    use crate::changelogs::Migration;
+    use crate::configuration_properties::DatasourceProperties;
+    use crate::driver::sqlite::DatasourceSqlite;
+    use crate::driver::{
+        Driver, DriverFactory, Execution, RmigEmptyResult, HISTORY_TABLE, LOCK_TIMEOUT,
+        RMIG_VERSION,
+    };
+    use crate::error::Error;
+    use crate::history::HistorySource;
+    use crate::naming::Version;
+    use crate::test_utils::{MigrationBuilder, TempDir};
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    #[tokio::test]
+    pub async fn create_core_table() -> RmigEmptyResult {
+        let sqlite = create_local_connection();
+        sqlite.validate_connection().await?;
+        sqlite.lock().await?;
+
+        assert!(sqlite.check_rmig_core_table().await.is_err());
+        sqlite.create_rmig_core_table().await?;
+        sqlite.check_rmig_core_table().await?;
+        // New core table already contains all upgrades.
+        sqlite.upgrade_rmig_core_table().await?;
+        assert_eq!(vec![3], core_versions(&sqlite).await);
+
+        sqlite.unlock().await?;
+        // Lock is released and can be acquired again.
+        sqlite.lock().await?;
+        sqlite.unlock().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn lock_timeout() -> RmigEmptyResult {
+        let dir = TempDir::new("rmig_sqlite_lock").unwrap();
+        let url = format!("sqlite://{}/rmig.db", dir.path.display());
+        let mut env = HashMap::new();
+        env.insert(LOCK_TIMEOUT.to_string(), "1".to_string());
+        let first = DatasourceSqlite::new(&DatasourceProperties::new(None, url.clone(), None))?;
+        let second = DatasourceSqlite::new(&DatasourceProperties::new(None, url, Some(env)))?;
+
+        first.lock().await?;
+        match second.lock().await {
+            Err(Error::LockError(message)) => assert!(message.contains("pid"), "{}", message),
+            _ => panic!("Lock must not be acquired."),
+        }
+        // Lock row of another datasource is not deleted.
+        second.unlock().await?;
+        first.unlock().await?;
+        second.lock().await?;
+        second.unlock().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn invalid_properties() {
+        let mut env = HashMap::new();
+        env.insert(LOCK_TIMEOUT.to_string(), "1m".to_string());
+        let properties = DatasourceProperties::new(None, "sqlite::memory:".to_string(), Some(env));
+        assert!(matches!(
+            DatasourceSqlite::new(&properties),
+            Err(Error::ParseError(_, _))
+        ));
+        let properties =
+            DatasourceProperties::new(None, "sqlite://rmig.db?mode=unknown".to_string(), None);
+        assert!(matches!(
+            DatasourceSqlite::new(&properties),
+            Err(Error::CreatingDatasourceError(_))
+        ));
+    }
+
+    #[tokio::test]
+    pub async fn migrate_and_add_new_migration() -> RmigEmptyResult {
+        let sqlite = create_local_connection();
+        let name = "test_dir".to_string();
+        let hash = "md5".to_string();
+
+        sqlite.lock().await?;
+        sqlite.create_rmig_core_table().await?;
+
+        let migration = create_migration(name.to_owned(), hash.to_owned());
+        match sqlite
+            .find_in_core_table(name.to_owned(), hash.to_owned())
+            .await
+        {
+            Err(Error::RowError(_)) => {}
+            _ => panic!("Migration must not exist."),
+        }
+        sqlite.migrate(vec![&migration]).await?;
+
+        sqlite
+            .find_in_core_table(name.to_owned(), hash.to_owned())
+            .await?;
+        match sqlite
+            .find_in_core_table(name.to_owned(), "changed".to_string())
+            .await
+        {
+            Err(Error::HashUniqueError(_)) => {}
+            _ => panic!("Hash must be changed."),
+        }
+
+        let applied = sqlite.find_all_in_core_table().await?;
+        assert_eq!(1, applied.len());
+        assert_eq!(name, applied[0].name);
+        assert_eq!(1, applied[0].order);
+
+        let row: (i32,) =
+            sqlx::query_as("SELECT 150 as result FROM rmig_test WHERE test = '123456'")
+                .fetch_one(&*sqlite.pool)
+                .await
+                .unwrap();
+        assert_eq!(150, row.0);
+
+        let mut changed = create_migration(name.to_owned(), "changed".to_string());
+        sqlite.update_hash_in_core_table(changed.clone()).await?;
+        sqlite
+            .find_in_core_table(name.to_owned(), "changed".to_string())
+            .await?;
+        changed.hash = hash.to_owned();
+        sqlite.update_hash_in_core_table(changed).await?;
+
+        let mut migration = MigrationBuilder::new(&name)
+            .hash(&hash)
+            .down_query("DROP TABLE rmig_test")
+            .build();
+        sqlite.migrate_down(&migration).await?;
+        assert!(sqlite.find_all_in_core_table().await?.is_empty());
+
+        // Failed execution is recorded, but migration is not applied.
+        migration.stage = Some("init".to_string());
+        migration.author = Some("admin".to_string());
+        sqlite
+            .add_new_migration(
+                migration.clone(),
+                Execution::failed(Duration::from_millis(5)),
+            )
+            .await?;
+        assert!(sqlite.find_all_in_core_table().await?.is_empty());
+        sqlite
+            .add_new_migration(migration, Execution::success(Duration::from_millis(7)))
+            .await?;
+        assert_eq!(1, sqlite.find_all_in_core_table().await?.len());
+        let row: (String, String, i64, String, String) = sqlx::query_as(
+            "SELECT STAGE, AUTHOR, EXEC_TIME_MS, STATUS, RMIG_VERSION FROM CHANGELOGS",
+        )
+        .fetch_one(&*sqlite.pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            (
+                "init".to_string(),
+                "admin".to_string(),
+                7,
+                "SUCCESS".to_string(),
+                RMIG_VERSION.to_string()
+            ),
+            row
+        );
+        sqlite.delete_from_core_table(name.to_owned()).await?;
+        assert!(sqlite.find_all_in_core_table().await?.is_empty());
+
+        sqlite.unlock().await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn single_row_per_migration() -> RmigEmptyResult {
+        let sqlite = create_local_connection();
+        sqlite.create_rmig_core_table().await?;
+        let migration = create_migration("1.repeat.sql".to_string(), "md5".to_string());
+        sqlite
+            .add_new_migration(
+                migration.clone(),
+                Execution::success(Duration::from_millis(7)),
+            )
+            .await?;
+
+        // Failed re-run replaces successful execution.
+        sqlite
+            .add_new_migration(
+                migration.clone(),
+                Execution::failed(Duration::from_millis(5)),
+            )
+            .await?;
+        let rows: Vec<(i64, String)> =
+            sqlx::query_as("SELECT EXEC_TIME_MS, STATUS FROM CHANGELOGS")
+                .fetch_all(&*sqlite.pool)
+                .await
+                .unwrap();
+        assert_eq!(vec![(5, "FAILED".to_string())], rows);
+        assert!(sqlite.find_all_in_core_table().await?.is_empty());
+
+        // Unique FILENAME of core table.
+        // language=SQL
+        assert!(sqlx::query(
+            "INSERT INTO CHANGELOGS(FILENAME, ORDER_ID, HASH) VALUES ('1.repeat.sql', 1, 'md5');"
+        )
+        .execute(&*sqlite.pool)
+        .await
+        .is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn store_full_version() -> RmigEmptyResult {
+        let sqlite = create_local_connection();
+        sqlite.create_rmig_core_table().await?;
+        let mut first = create_migration("V1_2__first.sql".to_string(), "md5".to_string());
+        first.version = Version::parse("1.2");
+        let mut timestamp =
+            create_migration("V20230101120000__next.sql".to_string(), "md5".to_string());
+        timestamp.version = Version::parse("20230101120000");
+        timestamp.order = 20230101120000;
+        timestamp.query_list.clear();
+        sqlite.migrate(vec![&first, &timestamp]).await?;
+
+        let applied = sqlite.find_all_in_core_table().await?;
+        assert_eq!(
+            vec![(1, first.version), (20230101120000, timestamp.version)],
+            applied
+                .into_iter()
+                .map(|a| (a.order, a.version))
+                .collect::<Vec<(i64, Option<Version>)>>()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn migrate_all_or_nothing() -> RmigEmptyResult {
+        let sqlite = create_local_connection();
+        sqlite.create_rmig_core_table().await?;
+
+        let first = create_migration("1.first.sql".to_string(), "md5".to_string());
+        let mut broken = create_migration("2.broken.sql".to_string(), "md5".to_string());
+        broken.query_list[1].query = "INSERT INTO unknown(test) VALUES('1')".to_string();
+        assert!(sqlite.migrate(vec![&first, &broken]).await.is_err());
+        // Nothing is applied and recorded, so the first migration can be applied again.
+        assert!(sqlite.find_all_in_core_table().await?.is_empty());
+        sqlite.migrate(vec![&first]).await?;
+        assert_eq!(1, sqlite.find_all_in_core_table().await?.len());
+
+        // Without transaction the first statement is kept.
+        broken.query_list[0].query = "CREATE TABLE rmig_plain(test TEXT)".to_string();
+        assert!(sqlite
+            .migrate_without_transaction(broken.query_list.iter().collect())
+            .await
+            .is_err());
+        sqlx::query("SELECT test FROM rmig_plain")
+            .fetch_all(&*sqlite.pool)
+            .await
+            .unwrap();
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn find_all_with_same_date() -> RmigEmptyResult {
+        let sqlite = create_local_connection();
+        sqlite.create_rmig_core_table().await?;
+        // Rows share DATEEXEC, they are returned in order of insertion.
+        // language=SQL
+        sqlx::query(
+            "INSERT INTO CHANGELOGS(FILENAME, ORDER_ID, HASH, DATEEXEC) VALUES \
+             ('2.second.sql', 2, 'md5', '2021-01-01 00:00:00'), \
+             ('sub/1.first.sql', 1, 'md5', '2021-01-01 00:00:00'), \
+             ('0.before.sql', 0, 'md5', '2020-12-31 00:00:00');",
+        )
+        .execute(&*sqlite.pool)
+        .await
+        .map_err(|e| Error::SQLError(format!("{:?}", e)))?;
+
+        let applied = sqlite.find_all_in_core_table().await?;
+        assert_eq!(
+            vec!["0.before.sql", "2.second.sql", "sub/1.first.sql"],
+            applied
+                .iter()
+                .map(|a| a.name.as_str())
+                .collect::<Vec<&str>>()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn script_is_recognised_as_applied() -> RmigEmptyResult {
+        let sqlite = create_local_connection();
+        let migration = create_migration("test_dir".to_string(), "md5".to_string());
+
+        // Script is applied by hand, without [Driver::migrate].
+        let script = format!(
+            "{};\n{}",
+            sqlite.rmig_core_table_sql()?.trim(),
+            sqlite.script_migration(&migration)
+        );
+        sqlx::query(&*script)
+            .execute(&*sqlite.pool)
+            .await
+            .map_err(|e| Error::SQLError(format!("{:?}", e)))?;
+
+        sqlite
+            .find_in_core_table("test_dir".to_string(), "md5".to_string())
+            .await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn upgrade_core_table() -> RmigEmptyResult {
+        let sqlite = create_local_connection();
+        // Core table of the first version.
+        // language=SQL
+        sqlx::query(
+            "CREATE TABLE CHANGELOGS(FILENAME TEXT, ORDER_ID INT, HASH TEXT, DATEEXEC TIMESTAMP DEFAULT CURRENT_TIMESTAMP);\n\
+             INSERT INTO CHANGELOGS(FILENAME, ORDER_ID, HASH) VALUES ('test_dir', 1, 'md5');",
+        )
+        .execute(&*sqlite.pool)
+        .await
+        .map_err(|e| Error::SQLError(format!("{:?}", e)))?;
+
+        // Core table of the first version is read without upgrade.
+        assert_eq!(1, sqlite.find_all_in_core_table().await?.len());
+        let upgrade = sqlite.upgrade_rmig_core_table_sql().await?;
+        assert!(upgrade[0].starts_with("CREATE TABLE IF NOT EXISTS CHANGELOGS_VERSION"));
+        assert_eq!(
+            "INSERT INTO CHANGELOGS_VERSION(VERSION) VALUES (1);",
+            upgrade[1]
+        );
+        assert!(upgrade[2].starts_with("ALTER TABLE CHANGELOGS ADD COLUMN STAGE TEXT;"));
+        assert!(upgrade[2].ends_with("RMIG_VERSION TEXT;"));
+        assert_eq!(
+            "INSERT INTO CHANGELOGS_VERSION(VERSION) VALUES (2);",
+            upgrade[3]
+        );
+        assert_eq!(
+            "ALTER TABLE CHANGELOGS ADD COLUMN VERSION TEXT;",
+            upgrade[4]
+        );
+        assert_eq!(
+            "INSERT INTO CHANGELOGS_VERSION(VERSION) VALUES (3);",
+            upgrade[5]
+        );
+        assert_eq!(6, upgrade.len());
+        assert!(!sqlite.core_columns().await?.contains(&"STATUS".to_string()));
+
+        sqlite.upgrade_rmig_core_table().await?;
+        assert_eq!(vec![1, 2, 3], core_versions(&sqlite).await);
+        assert!(sqlite.upgrade_rmig_core_table_sql().await?.is_empty());
+        // Upgrade is applied once.
+        sqlite.upgrade_rmig_core_table().await?;
+        assert_eq!(vec![1, 2, 3], core_versions(&sqlite).await);
+        sqlite
+            .find_in_core_table("test_dir".to_string(), "md5".to_string())
+            .await?;
+        // Row written before upgrade has no version.
+        assert_eq!(None, sqlite.find_all_in_core_table().await?[0].version);
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn repeat_interrupted_upgrade() -> RmigEmptyResult {
+        let sqlite = create_local_connection();
+        // Upgrade script is applied, but its version is not written.
+        // language=SQL
+        sqlx::query(
+            "CREATE TABLE CHANGELOGS(FILENAME TEXT, ORDER_ID INT, HASH TEXT, DATEEXEC TIMESTAMP DEFAULT CURRENT_TIMESTAMP);\n\
+             CREATE TABLE CHANGELOGS_VERSION(VERSION INTEGER NOT NULL, DATEEXEC TIMESTAMP DEFAULT CURRENT_TIMESTAMP);\n\
+             INSERT INTO CHANGELOGS_VERSION(VERSION) VALUES (1);\n\
+             ALTER TABLE CHANGELOGS ADD COLUMN STATUS TEXT NOT NULL DEFAULT 'SUCCESS';",
+        )
+        .execute(&*sqlite.pool)
+        .await
+        .map_err(|e| Error::SQLError(format!("{:?}", e)))?;
+
+        sqlite.upgrade_rmig_core_table().await?;
+        assert_eq!(vec![1, 2, 3], core_versions(&sqlite).await);
+
+        // Broken version table is an error, it is not read as missing.
+        // language=SQL
+        sqlx::query("DROP TABLE CHANGELOGS_VERSION; CREATE TABLE CHANGELOGS_VERSION(ID INTEGER);")
+            .execute(&*sqlite.pool)
+            .await
+            .map_err(|e| Error::SQLError(format!("{:?}", e)))?;
+        assert!(matches!(
+            sqlite.upgrade_rmig_core_table().await,
+            Err(Error::SQLError(_))
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    pub async fn custom_history_table() -> RmigEmptyResult {
+        let mut env = HashMap::new();
+        env.insert(HISTORY_TABLE.to_string(), "APP_HISTORY".to_string());
+        let properties = DatasourceProperties::new(
+            Some("Local sqlite ds".to_string()),
+            "sqlite::memory:".to_string(),
+            Some(env),
+        );
+        let sqlite = DatasourceSqlite::new(&properties)?;
+        assert!(sqlite.check_rmig_core_table().await.is_err());
+        sqlite.create_rmig_core_table().await?;
+        sqlite.check_rmig_core_table().await?;
+
+        let migration = create_migration("test_dir".to_string(), "md5".to_string());
+        sqlite.migrate(vec![&migration]).await?;
+        sqlite
+            .find_in_core_table("test_dir".to_string(), "md5".to_string())
+            .await?;
+        let versions: Vec<i32> =
+            sqlx::query_scalar("SELECT VERSION FROM APP_HISTORY_VERSION ORDER BY VERSION")
+                .fetch_all(&*sqlite.pool)
+                .await
+                .unwrap();
+        assert_eq!(vec![3], versions);
+        // Default core table is not created.
+        assert!(sqlx::query("SELECT 1 FROM CHANGELOGS")
+            .fetch_all(&*sqlite.pool)
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    async fn core_versions(sqlite: &DatasourceSqlite) -> Vec<i32> {
+        sqlx::query_scalar("SELECT VERSION FROM CHANGELOGS_VERSION ORDER BY VERSION")
+            .fetch_all(&*sqlite.pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    pub async fn find_flyway_history() -> RmigEmptyResult {
+        let sqlite = create_local_connection();
+        // language=SQL
+        sqlx::query(
+            "CREATE TABLE flyway_schema_history(installed_rank INT, script TEXT, success BOOLEAN);\n\
+             INSERT INTO flyway_schema_history VALUES (2, 'V2__update.sql', 1), (1, 'V1__init.sql', 1), (3, 'V3__failed.sql', 0);",
+        )
+        .execute(&*sqlite.pool)
+        .await
+        .map_err(|e| Error::SQLError(format!("{:?}", e)))?;
+
+        assert_eq!(
+            vec!["V1__init.sql".to_string(), "V2__update.sql".to_string()],
+            sqlite.find_in_history_table(&HistorySource::Flyway).await?
+        );
+        assert!(sqlite
+            .find_in_history_table(&HistorySource::Liquibase)
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    fn create_migration(name: String, hash: String) -> Migration {
+        MigrationBuilder::new(&name)
+            .hash(&hash)
+            .query("CREATE TABLE rmig_test(test TEXT)")
+            .query("INSERT INTO rmig_test(test) VALUES('123456')")
+            .build()
+    }
+
+    fn create_local_connection() -> DatasourceSqlite {
+        let properties = DatasourceProperties::new(
+            Some("Local sqlite ds".to_string()),
+            "sqlite::memory:".to_string(),
+            None,
+        );
+        DatasourceSqlite::new(&properties).unwrap()
+    }
+}
@@ -20,4 +20,16 @@ pub enum Error {
     SQLError(String),
     #[error("File with name {0} exists, but hash have been changed.")]
     HashUniqueError(String),
+    #[error("Migration failed on datasource(s): {0}.")]
+    DatasourcesFailed(String),
+    #[error("Rollback is not possible. {0}")]
+    RollbackError(String),
+    #[error("Stage dependencies are not resolvable. {0}")]
+    StageDependencyError(String),
+    #[error("Order of migration is not unique. {0}")]
+    DuplicateOrderError(String),
+    #[error("Baseline is not possible. {0}")]
+    BaselineError(String),
+    #[error("Lock is not acquired. {0}")]
+    LockError(String),
 }
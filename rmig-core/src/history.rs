@@ -0,0 +1,157 @@
+use crate::changelogs::Migration;
+use crate::driver::AppliedMigration;
+use crate::enum_str;
+use crate::error::Error;
+use std::str::FromStr;
+
+enum_str! {
+    pub enum HistorySource {
+        Flyway = 0x00000,
+        Liquibase = 0x00001,
+    }
+}
+
+impl FromStr for HistorySource {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let source = s.trim().to_lowercase();
+        vec![HistorySource::Flyway, HistorySource::Liquibase]
+            .into_iter()
+            .find(|h| h.name().to_lowercase().eq(&source))
+            .ok_or_else(|| {
+                Error::ParseFileError(format!(
+                    "Unknown history source '{}'. Use flyway or liquibase.",
+                    s
+                ))
+            })
+    }
+}
+
+impl HistorySource {
+    /// Select of successfully applied scripts, in order of execution.
+    /// Flyway creates lowercase table, [quote] is required for dialects with uppercase identifiers (Oracle).
+    /// [success] is boolean true literal of dialect.
+    pub fn select_sql(&self, quote: &str, success: &str) -> String {
+        match self {
+            // language=SQL
+            HistorySource::Flyway => format!(
+                "SELECT {q}script{q} FROM {q}flyway_schema_history{q} WHERE {q}success{q} = {} AND {q}script{q} IS NOT NULL ORDER BY {q}installed_rank{q}",
+                success,
+                q = quote
+            ),
+            // language=SQL
+            HistorySource::Liquibase => "SELECT FILENAME FROM DATABASECHANGELOG WHERE EXECTYPE IN ('EXECUTED', 'RERAN', 'MARK_RAN') ORDER BY ORDEREXECUTED".to_string(),
+        }
+    }
+}
+
+/// Result of matching scripts of history table with migrations on disk.
+#[derive(Clone, Debug)]
+pub struct HistoryImport<'a> {
+    /// Migrations to insert into core table, in order of history.
+    pub migrations: Vec<&'a Migration>,
+    /// Migrations, which already exist in core table.
+    pub skipped: Vec<&'a Migration>,
+    /// Scripts of history without migration on disk (baseline, java migrations and etc.)
+    pub unmatched: Vec<String>,
+}
+
+impl<'a> HistoryImport<'a> {
+    /// Script is matched with migration by relative path or by file name.
+    /// Liquibase writes several changesets of one file, every migration is imported once.
+    pub fn new(
+        scripts: Vec<String>,
+        migrations: &[&'a Migration],
+        applied: &[AppliedMigration],
+    ) -> Self {
+        let mut import = HistoryImport {
+            migrations: vec![],
+            skipped: vec![],
+            unmatched: vec![],
+        };
+        for script in scripts {
+            let found = migrations
+                .iter()
+                .find(|m| m.name.ends_with(&*format!("/{}", script)))
+                .or_else(|| {
+                    migrations
+                        .iter()
+                        .find(|m| file_name(&m.name) == file_name(&script))
+                });
+            match found {
+                None => {
+                    if !import.unmatched.contains(&script) {
+                        import.unmatched.push(script)
+                    }
+                }
+                Some(m) if applied.iter().any(|a| a.name == m.name) => {
+                    if !import.skipped.iter().any(|s| s.name == m.name) {
+                        import.skipped.push(m)
+                    }
+                }
+                Some(m) => {
+                    if !import.migrations.iter().any(|i| i.name == m.name) {
+                        import.migrations.push(m)
+                    }
+                }
+            }
+        }
+        import
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit(|c| c == '/' || c == '\\' || c == ':')
+        .next()
+        .unwrap_or(path)
+}
+
+#[cfg(test)]
+mod local_test {
+    use crate::changelogs::Migration;
+    use crate::driver::AppliedMigration;
+    use crate::history::{HistoryImport, HistorySource};
+    use crate::test_utils::MigrationBuilder;
+    use std::str::FromStr;
+
+    fn migration(name: &str, order: i64) -> Migration {
+        MigrationBuilder::new(name).order(order).build()
+    }
+
+    #[test]
+    pub fn match_history_scripts() -> anyhow::Result<()> {
+        let m1 = migration("./migration/V1__init.sql", 1);
+        let m2 = migration("./migration/billing/V2__invoice.sql", 2);
+        let m3 = migration("./migration/V3__user.sql", 3);
+        let import = HistoryImport::new(
+            vec![
+                "<< Flyway Baseline >>".to_string(),
+                "V1__init.sql".to_string(),
+                "billing/V2__invoice.sql".to_string(),
+                "classpath:db/V3__user.sql".to_string(),
+                "classpath:db/V3__user.sql".to_string(),
+            ],
+            &[&m1, &m2, &m3],
+            &[AppliedMigration {
+                name: "./migration/V1__init.sql".to_string(),
+                order: 1,
+                hash: "md5".to_string(),
+                version: None,
+            }],
+        );
+
+        let names = |m: &Vec<&Migration>| m.iter().map(|m| m.order).collect::<Vec<i64>>();
+        assert_eq!(vec![2, 3], names(&import.migrations));
+        assert_eq!(vec![1], names(&import.skipped));
+        assert_eq!(vec!["<< Flyway Baseline >>".to_string()], import.unmatched);
+
+        assert!(HistorySource::from_str("Liquibase").is_ok());
+        assert!(HistorySource::from_str("alembic").is_err());
+        assert_eq!(
+            "SELECT \"script\" FROM \"flyway_schema_history\" WHERE \"success\" = 1 AND \"script\" IS NOT NULL ORDER BY \"installed_rank\"",
+            HistorySource::Flyway.select_sql("\"", "1")
+        );
+        Ok(())
+    }
+}
@@ -1,17 +1,33 @@
 {% if SCHEMA_ADMIN %}
-CREATE TABLE IF NOT EXISTS {{ SCHEMA_ADMIN }}.CHANGELOGS
+CREATE TABLE IF NOT EXISTS {{ SCHEMA_ADMIN }}.{{ HISTORY_TABLE }}
 (
-    FILENAME TEXT NOT NULL,
-    ORDER_ID INT  NOT NULL,
-    HASH     TEXT NOT NULL,
-    DATEEXEC TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+    FILENAME     VARCHAR(255) NOT NULL UNIQUE,
+    ORDER_ID     BIGINT    NOT NULL,
+    HASH         TEXT      NOT NULL,
+    DATEEXEC     TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    STAGE        VARCHAR(255),
+    AUTHOR       VARCHAR(255),
+    EXEC_TIME_MS BIGINT,
+    STATUS       VARCHAR(16) NOT NULL DEFAULT 'SUCCESS',
+    EXECUTED_BY  VARCHAR(255),
+    CLIENT_HOST  VARCHAR(255),
+    RMIG_VERSION VARCHAR(64),
+    VERSION      VARCHAR(255)
 )
 {% else  %}
-CREATE TABLE IF NOT EXISTS CHANGELOGS
+CREATE TABLE IF NOT EXISTS {{ HISTORY_TABLE }}
 (
-    FILENAME TEXT NOT NULL,
-    ORDER_ID INT  NOT NULL,
-    HASH     TEXT NOT NULL,
-    DATEEXEC TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+    FILENAME     VARCHAR(255) NOT NULL UNIQUE,
+    ORDER_ID     BIGINT    NOT NULL,
+    HASH         TEXT      NOT NULL,
+    DATEEXEC     TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    STAGE        VARCHAR(255),
+    AUTHOR       VARCHAR(255),
+    EXEC_TIME_MS BIGINT,
+    STATUS       VARCHAR(16) NOT NULL DEFAULT 'SUCCESS',
+    EXECUTED_BY  VARCHAR(255),
+    CLIENT_HOST  VARCHAR(255),
+    RMIG_VERSION VARCHAR(64),
+    VERSION      VARCHAR(255)
 )
 {% endif %}
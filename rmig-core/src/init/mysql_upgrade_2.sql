@@ -0,0 +1,19 @@
+{% if SCHEMA_ADMIN %}
+ALTER TABLE {{ SCHEMA_ADMIN }}.{{ HISTORY_TABLE }}
+    ADD COLUMN STAGE VARCHAR(255),
+    ADD COLUMN AUTHOR VARCHAR(255),
+    ADD COLUMN EXEC_TIME_MS BIGINT,
+    ADD COLUMN STATUS VARCHAR(16) NOT NULL DEFAULT 'SUCCESS',
+    ADD COLUMN EXECUTED_BY VARCHAR(255),
+    ADD COLUMN CLIENT_HOST VARCHAR(255),
+    ADD COLUMN RMIG_VERSION VARCHAR(64)
+{% else  %}
+ALTER TABLE {{ HISTORY_TABLE }}
+    ADD COLUMN STAGE VARCHAR(255),
+    ADD COLUMN AUTHOR VARCHAR(255),
+    ADD COLUMN EXEC_TIME_MS BIGINT,
+    ADD COLUMN STATUS VARCHAR(16) NOT NULL DEFAULT 'SUCCESS',
+    ADD COLUMN EXECUTED_BY VARCHAR(255),
+    ADD COLUMN CLIENT_HOST VARCHAR(255),
+    ADD COLUMN RMIG_VERSION VARCHAR(64)
+{% endif %}
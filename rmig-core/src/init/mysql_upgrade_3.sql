@@ -0,0 +1,9 @@
+{% if SCHEMA_ADMIN %}
+ALTER TABLE {{ SCHEMA_ADMIN }}.{{ HISTORY_TABLE }}
+    MODIFY COLUMN ORDER_ID BIGINT NOT NULL,
+    ADD COLUMN VERSION VARCHAR(255)
+{% else  %}
+ALTER TABLE {{ HISTORY_TABLE }}
+    MODIFY COLUMN ORDER_ID BIGINT NOT NULL,
+    ADD COLUMN VERSION VARCHAR(255)
+{% endif %}
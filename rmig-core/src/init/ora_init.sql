@@ -1,17 +1,33 @@
 {% if SCHEMA_ADMIN %}
-CREATE TABLE {{ SCHEMA_ADMIN }}.CHANGELOGS
+CREATE TABLE {{ SCHEMA_ADMIN }}.{{ HISTORY_TABLE }}
 (
-    FILENAME VARCHAR(255) NOT NULL,
-    ORDER_ID INTEGER NOT NULL,
-    HASH     VARCHAR(255) NOT NULL,
-    DATEEXEC TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+    FILENAME     VARCHAR2(255) NOT NULL UNIQUE,
+    ORDER_ID     INTEGER NOT NULL,
+    HASH         VARCHAR2(255) NOT NULL,
+    DATEEXEC     TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    STAGE        VARCHAR2(255),
+    AUTHOR       VARCHAR2(255),
+    EXEC_TIME_MS NUMBER(19),
+    STATUS       VARCHAR2(16) DEFAULT 'SUCCESS' NOT NULL,
+    EXECUTED_BY  VARCHAR2(255),
+    CLIENT_HOST  VARCHAR2(255),
+    RMIG_VERSION VARCHAR2(64),
+    VERSION      VARCHAR2(255)
 )
 {% else  %}
-CREATE TABLE CHANGELOGS
+CREATE TABLE {{ HISTORY_TABLE }}
 (
-    FILENAME VARCHAR(255) NOT NULL,
-    ORDER_ID INTEGER NOT NULL,
-    HASH     VARCHAR(255) NOT NULL,
-    DATEEXEC TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+    FILENAME     VARCHAR2(255) NOT NULL UNIQUE,
+    ORDER_ID     INTEGER NOT NULL,
+    HASH         VARCHAR2(255) NOT NULL,
+    DATEEXEC     TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    STAGE        VARCHAR2(255),
+    AUTHOR       VARCHAR2(255),
+    EXEC_TIME_MS NUMBER(19),
+    STATUS       VARCHAR2(16) DEFAULT 'SUCCESS' NOT NULL,
+    EXECUTED_BY  VARCHAR2(255),
+    CLIENT_HOST  VARCHAR2(255),
+    RMIG_VERSION VARCHAR2(64),
+    VERSION      VARCHAR2(255)
 )
 {% endif %}
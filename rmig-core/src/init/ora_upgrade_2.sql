@@ -0,0 +1,23 @@
+{% if SCHEMA_ADMIN %}
+ALTER TABLE {{ SCHEMA_ADMIN }}.{{ HISTORY_TABLE }} ADD
+(
+    STAGE VARCHAR2(255),
+    AUTHOR VARCHAR2(255),
+    EXEC_TIME_MS NUMBER(19),
+    STATUS VARCHAR2(16) DEFAULT 'SUCCESS' NOT NULL,
+    EXECUTED_BY VARCHAR2(255),
+    CLIENT_HOST VARCHAR2(255),
+    RMIG_VERSION VARCHAR2(64)
+)
+{% else  %}
+ALTER TABLE {{ HISTORY_TABLE }} ADD
+(
+    STAGE VARCHAR2(255),
+    AUTHOR VARCHAR2(255),
+    EXEC_TIME_MS NUMBER(19),
+    STATUS VARCHAR2(16) DEFAULT 'SUCCESS' NOT NULL,
+    EXECUTED_BY VARCHAR2(255),
+    CLIENT_HOST VARCHAR2(255),
+    RMIG_VERSION VARCHAR2(64)
+)
+{% endif %}
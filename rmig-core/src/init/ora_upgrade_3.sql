@@ -0,0 +1,11 @@
+{% if SCHEMA_ADMIN %}
+ALTER TABLE {{ SCHEMA_ADMIN }}.{{ HISTORY_TABLE }} ADD
+(
+    VERSION VARCHAR2(255)
+)
+{% else  %}
+ALTER TABLE {{ HISTORY_TABLE }} ADD
+(
+    VERSION VARCHAR2(255)
+)
+{% endif %}
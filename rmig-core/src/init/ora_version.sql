@@ -0,0 +1,13 @@
+{% if SCHEMA_ADMIN %}
+CREATE TABLE {{ SCHEMA_ADMIN }}.{{ HISTORY_TABLE }}_VERSION
+(
+    VERSION  INTEGER   NOT NULL,
+    DATEEXEC TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+)
+{% else  %}
+CREATE TABLE {{ HISTORY_TABLE }}_VERSION
+(
+    VERSION  INTEGER   NOT NULL,
+    DATEEXEC TIMESTAMP DEFAULT CURRENT_TIMESTAMP NOT NULL
+)
+{% endif %}
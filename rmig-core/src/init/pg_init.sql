@@ -1,17 +1,33 @@
 {% if SCHEMA_ADMIN %}
-CREATE TABLE IF NOT EXISTS {{ SCHEMA_ADMIN }}.CHANGELOGS
+CREATE TABLE IF NOT EXISTS {{ SCHEMA_ADMIN }}.{{ HISTORY_TABLE }}
 (
-    FILENAME TEXT NOT NULL,
-    ORDER_ID INT  NOT NULL,
-    HASH     TEXT NOT NULL,
-    DATEEXEC TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+    FILENAME     TEXT      NOT NULL UNIQUE,
+    ORDER_ID     BIGINT    NOT NULL,
+    HASH         TEXT      NOT NULL,
+    DATEEXEC     TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    STAGE        TEXT,
+    AUTHOR       TEXT,
+    EXEC_TIME_MS BIGINT,
+    STATUS       TEXT      NOT NULL DEFAULT 'SUCCESS',
+    EXECUTED_BY  TEXT,
+    CLIENT_HOST  TEXT,
+    RMIG_VERSION TEXT,
+    VERSION      TEXT
 )
 {% else  %}
-CREATE TABLE IF NOT EXISTS CHANGELOGS
+CREATE TABLE IF NOT EXISTS {{ HISTORY_TABLE }}
 (
-    FILENAME TEXT NOT NULL,
-    ORDER_ID INT  NOT NULL,
-    HASH     TEXT NOT NULL,
-    DATEEXEC TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+    FILENAME     TEXT      NOT NULL UNIQUE,
+    ORDER_ID     BIGINT    NOT NULL,
+    HASH         TEXT      NOT NULL,
+    DATEEXEC     TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    STAGE        TEXT,
+    AUTHOR       TEXT,
+    EXEC_TIME_MS BIGINT,
+    STATUS       TEXT      NOT NULL DEFAULT 'SUCCESS',
+    EXECUTED_BY  TEXT,
+    CLIENT_HOST  TEXT,
+    RMIG_VERSION TEXT,
+    VERSION      TEXT
 )
 {% endif %}
@@ -0,0 +1,19 @@
+{% if SCHEMA_ADMIN %}
+ALTER TABLE {{ SCHEMA_ADMIN }}.{{ HISTORY_TABLE }}
+    ADD COLUMN IF NOT EXISTS STAGE TEXT,
+    ADD COLUMN IF NOT EXISTS AUTHOR TEXT,
+    ADD COLUMN IF NOT EXISTS EXEC_TIME_MS BIGINT,
+    ADD COLUMN IF NOT EXISTS STATUS TEXT NOT NULL DEFAULT 'SUCCESS',
+    ADD COLUMN IF NOT EXISTS EXECUTED_BY TEXT,
+    ADD COLUMN IF NOT EXISTS CLIENT_HOST TEXT,
+    ADD COLUMN IF NOT EXISTS RMIG_VERSION TEXT
+{% else  %}
+ALTER TABLE {{ HISTORY_TABLE }}
+    ADD COLUMN IF NOT EXISTS STAGE TEXT,
+    ADD COLUMN IF NOT EXISTS AUTHOR TEXT,
+    ADD COLUMN IF NOT EXISTS EXEC_TIME_MS BIGINT,
+    ADD COLUMN IF NOT EXISTS STATUS TEXT NOT NULL DEFAULT 'SUCCESS',
+    ADD COLUMN IF NOT EXISTS EXECUTED_BY TEXT,
+    ADD COLUMN IF NOT EXISTS CLIENT_HOST TEXT,
+    ADD COLUMN IF NOT EXISTS RMIG_VERSION TEXT
+{% endif %}
@@ -0,0 +1,9 @@
+{% if SCHEMA_ADMIN %}
+ALTER TABLE {{ SCHEMA_ADMIN }}.{{ HISTORY_TABLE }}
+    ALTER COLUMN ORDER_ID TYPE BIGINT,
+    ADD COLUMN IF NOT EXISTS VERSION TEXT
+{% else  %}
+ALTER TABLE {{ HISTORY_TABLE }}
+    ALTER COLUMN ORDER_ID TYPE BIGINT,
+    ADD COLUMN IF NOT EXISTS VERSION TEXT
+{% endif %}
@@ -0,0 +1,33 @@
+{% if SCHEMA_ADMIN %}
+CREATE TABLE IF NOT EXISTS {{ SCHEMA_ADMIN }}.{{ HISTORY_TABLE }}
+(
+    FILENAME     TEXT      NOT NULL UNIQUE,
+    ORDER_ID     INTEGER   NOT NULL,
+    HASH         TEXT      NOT NULL,
+    DATEEXEC     TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    STAGE        TEXT,
+    AUTHOR       TEXT,
+    EXEC_TIME_MS INTEGER,
+    STATUS       TEXT      NOT NULL DEFAULT 'SUCCESS',
+    EXECUTED_BY  TEXT,
+    CLIENT_HOST  TEXT,
+    RMIG_VERSION TEXT,
+    VERSION      TEXT
+)
+{% else  %}
+CREATE TABLE IF NOT EXISTS {{ HISTORY_TABLE }}
+(
+    FILENAME     TEXT      NOT NULL UNIQUE,
+    ORDER_ID     INTEGER   NOT NULL,
+    HASH         TEXT      NOT NULL,
+    DATEEXEC     TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    STAGE        TEXT,
+    AUTHOR       TEXT,
+    EXEC_TIME_MS INTEGER,
+    STATUS       TEXT      NOT NULL DEFAULT 'SUCCESS',
+    EXECUTED_BY  TEXT,
+    CLIENT_HOST  TEXT,
+    RMIG_VERSION TEXT,
+    VERSION      TEXT
+)
+{% endif %}
@@ -0,0 +1,17 @@
+{% if SCHEMA_ADMIN %}
+ALTER TABLE {{ SCHEMA_ADMIN }}.{{ HISTORY_TABLE }} ADD COLUMN STAGE TEXT;
+ALTER TABLE {{ SCHEMA_ADMIN }}.{{ HISTORY_TABLE }} ADD COLUMN AUTHOR TEXT;
+ALTER TABLE {{ SCHEMA_ADMIN }}.{{ HISTORY_TABLE }} ADD COLUMN EXEC_TIME_MS INTEGER;
+ALTER TABLE {{ SCHEMA_ADMIN }}.{{ HISTORY_TABLE }} ADD COLUMN STATUS TEXT NOT NULL DEFAULT 'SUCCESS';
+ALTER TABLE {{ SCHEMA_ADMIN }}.{{ HISTORY_TABLE }} ADD COLUMN EXECUTED_BY TEXT;
+ALTER TABLE {{ SCHEMA_ADMIN }}.{{ HISTORY_TABLE }} ADD COLUMN CLIENT_HOST TEXT;
+ALTER TABLE {{ SCHEMA_ADMIN }}.{{ HISTORY_TABLE }} ADD COLUMN RMIG_VERSION TEXT;
+{% else  %}
+ALTER TABLE {{ HISTORY_TABLE }} ADD COLUMN STAGE TEXT;
+ALTER TABLE {{ HISTORY_TABLE }} ADD COLUMN AUTHOR TEXT;
+ALTER TABLE {{ HISTORY_TABLE }} ADD COLUMN EXEC_TIME_MS INTEGER;
+ALTER TABLE {{ HISTORY_TABLE }} ADD COLUMN STATUS TEXT NOT NULL DEFAULT 'SUCCESS';
+ALTER TABLE {{ HISTORY_TABLE }} ADD COLUMN EXECUTED_BY TEXT;
+ALTER TABLE {{ HISTORY_TABLE }} ADD COLUMN CLIENT_HOST TEXT;
+ALTER TABLE {{ HISTORY_TABLE }} ADD COLUMN RMIG_VERSION TEXT;
+{% endif %}
@@ -0,0 +1,5 @@
+{% if SCHEMA_ADMIN %}
+ALTER TABLE {{ SCHEMA_ADMIN }}.{{ HISTORY_TABLE }} ADD COLUMN VERSION TEXT;
+{% else  %}
+ALTER TABLE {{ HISTORY_TABLE }} ADD COLUMN VERSION TEXT;
+{% endif %}
@@ -0,0 +1,13 @@
+{% if SCHEMA_ADMIN %}
+CREATE TABLE IF NOT EXISTS {{ SCHEMA_ADMIN }}.{{ HISTORY_TABLE }}_VERSION
+(
+    VERSION  INTEGER   NOT NULL,
+    DATEEXEC TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+)
+{% else  %}
+CREATE TABLE IF NOT EXISTS {{ HISTORY_TABLE }}_VERSION
+(
+    VERSION  INTEGER   NOT NULL,
+    DATEEXEC TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+)
+{% endif %}
@@ -1,10 +1,21 @@
 #![feature(test)]
 
+pub mod baseline;
 pub mod changelogs;
+pub mod checksum;
 pub mod cli;
 pub mod configuration_properties;
 pub mod driver;
 pub mod error;
+pub mod history;
+pub mod naming;
+pub mod plan;
+pub mod rehash;
+pub mod repair;
+pub mod report;
+pub mod rollback;
+pub mod splitter;
+pub mod status;
 pub mod tera_manager;
 pub mod utils;
 mod test_utils;
@@ -0,0 +1,211 @@
+use crate::changelogs::is_down_file;
+use crate::error::Error;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+
+/// Naming scheme of migration files, property [naming] of changelog.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NamingStrategy {
+    /// [order.name.ext], undo script [order.name.down.ext].
+    Order,
+    /// Flyway conventions: [V1_2__name.ext], repeatable [R__name.ext], undo script [U1_2__name.ext].
+    Flyway,
+}
+
+impl Default for NamingStrategy {
+    fn default() -> Self {
+        NamingStrategy::Order
+    }
+}
+
+/// Version of migration. Parts are compared as numbers: 1.10 > 1.9, trailing zeros are ignored: 1.0 == 1.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+pub struct Version(Vec<u64>);
+
+/// Digits of [u64], every part of [Version::sort_key] is padded to them.
+const VERSION_PART_WIDTH: usize = 20;
+
+impl Version {
+    /// Parts separated by [.] or [_].
+    pub fn parse(version: &str) -> Option<Self> {
+        let mut parts = version
+            .split(|c| c == '.' || c == '_')
+            .map(|p| p.parse::<u64>().ok())
+            .collect::<Option<Vec<u64>>>()?;
+        while parts.len() > 1 && parts.last() == Some(&0) {
+            parts.pop();
+        }
+        Some(Version(parts))
+    }
+
+    /// The first part of version, [ORDER] of default naming.
+    pub fn major(&self) -> i64 {
+        self.0[0] as i64
+    }
+
+    /// Column VERSION of core table. Parts are padded with zeros, so text is sorted as versions,
+    /// the key is read back by [Version::parse].
+    pub fn sort_key(&self) -> String {
+        self.0
+            .iter()
+            .map(|p| format!("{:0width$}", p, width = VERSION_PART_WIDTH))
+            .collect::<Vec<String>>()
+            .join(".")
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let parts = self
+            .0
+            .iter()
+            .map(|p| p.to_string())
+            .collect::<Vec<String>>();
+        write!(f, "{}", parts.join("."))
+    }
+}
+
+/// Kind of migration file. Versioned migrations are executed before repeatable ones.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum MigrationFile {
+    /// Migration is applied once, in order of version.
+    Versioned(Version),
+    /// Migration is applied again, when its hash has been changed.
+    Repeatable,
+    /// Undo script of versioned migration, read together with it.
+    Undo(Version),
+}
+
+impl NamingStrategy {
+    pub fn parse(&self, path: &PathBuf) -> anyhow::Result<MigrationFile, Error> {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .ok_or_else(|| Error::ParseFileError("Filename is not readable.".to_owned()))?
+            .trim();
+        match self {
+            NamingStrategy::Order => {
+                let name_separate = name.split('.').collect::<Vec<&str>>();
+                // Extension .sql/.any
+                if name_separate.len() < 2 {
+                    return Err(Error::ParseError(
+                        name.to_owned(),
+                        "Error while read migration. Extension required.".to_string(),
+                    ));
+                }
+                let version = name_separate[0]
+                    .parse::<u64>()
+                    .map(|o| Version(vec![o]))
+                    .map_err(|_e| Error::ParseFileError("File name is not contains order. Please use format order.filename.any extension. For example: 1.init.sql".to_owned()))?;
+                if is_down_file(path) {
+                    Ok(MigrationFile::Undo(version))
+                } else {
+                    Ok(MigrationFile::Versioned(version))
+                }
+            }
+            NamingStrategy::Flyway => {
+                let error = || {
+                    Error::ParseFileError(format!("File name {} is not Flyway migration. Please use format V1_2__name.sql, R__name.sql or U1_2__name.sql.", name))
+                };
+                let (version, description) = name
+                    .get(1..)
+                    .and_then(|n| n.split_once("__"))
+                    .ok_or_else(error)?;
+                if description.is_empty() {
+                    return Err(error());
+                }
+                match (&name[..1], version) {
+                    ("R", "") => Ok(MigrationFile::Repeatable),
+                    ("V", v) => Version::parse(v)
+                        .map(MigrationFile::Versioned)
+                        .ok_or_else(error),
+                    ("U", v) => Version::parse(v).map(MigrationFile::Undo).ok_or_else(error),
+                    _ => Err(error()),
+                }
+            }
+        }
+    }
+
+    /// File name of undo script for migration [name].
+    pub fn undo_file_name(&self, name: &str) -> Option<String> {
+        match self {
+            NamingStrategy::Order => name
+                .rsplit_once('.')
+                .map(|(stem, ext)| format!("{}.down.{}", stem, ext)),
+            NamingStrategy::Flyway => name.strip_prefix('V').map(|n| format!("U{}", n)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod local_test {
+    use crate::naming::{MigrationFile, NamingStrategy, Version};
+    use std::path::PathBuf;
+
+    fn parse(naming: &NamingStrategy, name: &str) -> Option<MigrationFile> {
+        naming
+            .parse(&PathBuf::from(format!("./migration/{}", name)))
+            .ok()
+    }
+
+    fn version(v: &str) -> Version {
+        Version::parse(v).unwrap()
+    }
+
+    #[test]
+    pub fn parse_file_names() -> anyhow::Result<()> {
+        let order = NamingStrategy::Order;
+        assert_eq!(
+            Some(MigrationFile::Versioned(version("10"))),
+            parse(&order, "10.init.sql")
+        );
+        assert_eq!(
+            Some(MigrationFile::Undo(version("1"))),
+            parse(&order, "1.init.down.sql")
+        );
+        assert_eq!(None, parse(&order, "V1__init.sql"));
+        assert_eq!(
+            Some("1.init.down.sql".to_string()),
+            order.undo_file_name("1.init.sql")
+        );
+
+        let flyway = NamingStrategy::Flyway;
+        assert_eq!(
+            Some(MigrationFile::Versioned(version("1.2"))),
+            parse(&flyway, "V1_2__add_user.sql")
+        );
+        assert_eq!(
+            Some(MigrationFile::Versioned(version("1.2"))),
+            parse(&flyway, "V1.2.0__add_user.sql")
+        );
+        assert_eq!(
+            Some(MigrationFile::Repeatable),
+            parse(&flyway, "R__user_view.sql")
+        );
+        assert_eq!(
+            Some(MigrationFile::Undo(version("3"))),
+            parse(&flyway, "U3__add_user.sql")
+        );
+        assert_eq!(None, parse(&flyway, "V1_a__add_user.sql"));
+        assert_eq!(None, parse(&flyway, "R1__user_view.sql"));
+        assert_eq!(None, parse(&flyway, "1.init.sql"));
+        assert_eq!(
+            Some("U1_2__add_user.sql".to_string()),
+            flyway.undo_file_name("V1_2__add_user.sql")
+        );
+
+        assert!(version("1.10") > version("1.9"));
+        assert!(version("1.10").sort_key() > version("1.9").sort_key());
+        assert!(version("1").sort_key() < version("1.1").sort_key());
+        assert!(version("20230101120000").sort_key() > version("9.9").sort_key());
+        assert_eq!(
+            Some(version("1.10")),
+            Version::parse(&*version("1.10").sort_key())
+        );
+        assert!(MigrationFile::Versioned(version("100")) < MigrationFile::Repeatable);
+        assert_eq!("1.10", version("1_10").to_string());
+        Ok(())
+    }
+}
@@ -0,0 +1,211 @@
+use crate::changelogs::Migration;
+use crate::driver::AppliedMigration;
+use crate::error::Error;
+use std::fmt::{Display, Formatter};
+
+/// Action of run command for migration by row of core table.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Step {
+    /// Already applied.
+    Skip,
+    /// Not found in core table.
+    New,
+    /// Applied, but executed on every run [run_always].
+    Repeat,
+    /// Applied with another hash [run_on_change].
+    Changed,
+}
+
+impl Step {
+    /// Find migration by name in [applied] rows of core table.
+    /// Changed hash of migration without [run_on_change] is [Error::HashUniqueError].
+    pub fn of(m: &Migration, applied: &[AppliedMigration]) -> anyhow::Result<Step, Error> {
+        match applied.iter().find(|a| a.name.eq(&m.name)) {
+            None => Ok(Step::New),
+            Some(a) if a.hash.ne(&m.hash) => {
+                if m.is_run_on_change() {
+                    Ok(Step::Changed)
+                } else {
+                    Err(Error::HashUniqueError(format!(
+                        "Row with filename {} and hash {} not found. Hash has been changed. Please revert your changed, or set query option run_on_change.",
+                        &m.name, &m.hash
+                    )))
+                }
+            }
+            Some(_) if m.is_run_always() => Ok(Step::Repeat),
+            Some(_) => Ok(Step::Skip),
+        }
+    }
+}
+
+/// Pending migration with rendered queries, which will be executed by run command.
+#[derive(Clone, Debug)]
+pub struct PlannedMigration {
+    pub name: String,
+    pub order: i64,
+    pub queries: Vec<String>,
+}
+
+/// Dry-run result for one datasource: pending migrations in order of execution.
+#[derive(Clone, Debug)]
+pub struct PlanReport {
+    pub datasource: String,
+    pub migrations: Vec<PlannedMigration>,
+    /// Migrations with changed hash, run command fails on the first of them.
+    pub mismatched: Vec<String>,
+}
+
+impl PlanReport {
+    /// Collect migrations, which are not found in core table by name, and repeatable migrations
+    /// ([run_always], or [run_on_change] with changed hash).
+    /// Migrations keep changelog order, queries keep file order.
+    pub fn pending(
+        datasource: &str,
+        migrations: Vec<&Migration>,
+        applied: Vec<AppliedMigration>,
+    ) -> Self {
+        let mut planned = Vec::<PlannedMigration>::new();
+        let mut mismatched = Vec::<String>::new();
+
+        for m in migrations {
+            match Step::of(m, &applied) {
+                Ok(Step::Skip) => {}
+                Ok(_) => planned.push(PlannedMigration {
+                    name: m.name.to_owned(),
+                    order: m.order,
+                    queries: m.query_list.iter().map(|q| q.query.to_owned()).collect(),
+                }),
+                Err(_) => mismatched.push(m.name.to_owned()),
+            }
+        }
+
+        PlanReport {
+            datasource: datasource.to_owned(),
+            migrations: planned,
+            mismatched,
+        }
+    }
+}
+
+/// Printed as sql script, information lines are commented.
+impl Display for PlanReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "-- Datasource: {}", &self.datasource)?;
+        for name in self.mismatched.iter() {
+            writeln!(
+                f,
+                "-- WARNING: hash of {} has been changed, run will fail.",
+                name
+            )?;
+        }
+        for m in self.migrations.iter() {
+            writeln!(f, "-- Migration: {} (order {})", &m.name, m.order)?;
+            for q in m.queries.iter() {
+                writeln!(f, "{}", q)?;
+            }
+        }
+        write!(f, "-- Total: {} pending.", self.migrations.len())
+    }
+}
+
+#[cfg(test)]
+mod local_test {
+    use crate::changelogs::{Migration, QueryOptions};
+    use crate::driver::AppliedMigration;
+    use crate::error::Error;
+    use crate::plan::{PlanReport, Step};
+    use crate::test_utils::MigrationBuilder;
+
+    fn migration(name: &str, order: i64, hash: &str, queries: Vec<&str>) -> Migration {
+        queries
+            .into_iter()
+            .fold(
+                MigrationBuilder::new(name).order(order).hash(hash),
+                |b, q| b.query(q),
+            )
+            .build()
+    }
+
+    #[test]
+    pub fn plan_pending() -> anyhow::Result<()> {
+        let m1 = migration("1.init.sql", 1, "a", vec!["create table a(id int);"]);
+        let m2 = migration("2.update.sql", 2, "b", vec!["select 1;"]);
+        let m3 = migration(
+            "3.new.sql",
+            3,
+            "c",
+            vec!["create table c(id int);", "insert into c values (1);"],
+        );
+        let report = PlanReport::pending(
+            "localhost",
+            vec![&m1, &m2, &m3],
+            vec![
+                AppliedMigration {
+                    name: "1.init.sql".to_string(),
+                    order: 1,
+                    hash: "a".to_string(),
+                    version: None,
+                },
+                AppliedMigration {
+                    name: "2.update.sql".to_string(),
+                    order: 2,
+                    hash: "changed".to_string(),
+                    version: None,
+                },
+            ],
+        );
+
+        assert_eq!(1, report.migrations.len());
+        assert_eq!("3.new.sql", report.migrations[0].name.as_str());
+        assert_eq!(vec!["2.update.sql".to_string()], report.mismatched);
+        assert_eq!(
+            "-- Datasource: localhost\n\
+             -- WARNING: hash of 2.update.sql has been changed, run will fail.\n\
+             -- Migration: 3.new.sql (order 3)\n\
+             create table c(id int);\n\
+             insert into c values (1);\n\
+             -- Total: 1 pending.",
+            report.to_string()
+        );
+        Ok(())
+    }
+
+    #[test]
+    pub fn step_of_migration() -> anyhow::Result<()> {
+        let applied = vec![AppliedMigration {
+            name: "1.view.sql".to_string(),
+            order: 1,
+            hash: "a".to_string(),
+            version: None,
+        }];
+        let repeatable = |hash: &str, opts: QueryOptions| {
+            MigrationBuilder::new("1.view.sql")
+                .hash(hash)
+                .query_with("create or replace view v as select 1;", opts)
+                .build()
+        };
+        let mut run_always = QueryOptions::default();
+        run_always.run_always = Some(true);
+        let mut run_on_change = QueryOptions::default();
+        run_on_change.run_on_change = Some(true);
+
+        let new = migration("2.new.sql", 2, "b", vec!["select 1;"]);
+        assert_eq!(Step::New, Step::of(&new, &applied)?);
+        let same = repeatable("a", QueryOptions::default());
+        assert_eq!(Step::Skip, Step::of(&same, &applied)?);
+        let always = repeatable("a", run_always.clone());
+        assert_eq!(Step::Repeat, Step::of(&always, &applied)?);
+        let always = repeatable("changed", run_always);
+        assert_eq!(Step::Changed, Step::of(&always, &applied)?);
+        let on_change = repeatable("a", run_on_change.clone());
+        assert_eq!(Step::Skip, Step::of(&on_change, &applied)?);
+        let on_change = repeatable("changed", run_on_change);
+        assert_eq!(Step::Changed, Step::of(&on_change, &applied)?);
+        let changed = repeatable("changed", QueryOptions::default());
+        assert!(matches!(
+            Step::of(&changed, &applied),
+            Err(Error::HashUniqueError(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,118 @@
+use crate::changelogs::Migration;
+use crate::driver::AppliedMigration;
+use std::fmt::{Display, Formatter};
+
+/// Changes of core table for one datasource, made by rehash command after change of properties
+/// [checksum], [checksum_normalize] or [checksum_template].
+#[derive(Clone, Debug)]
+pub struct RehashPlan<'a> {
+    pub datasource: String,
+    /// Migrations with hash of previous versions in core table, hash is replaced by the new one.
+    pub updates: Vec<&'a Migration>,
+    /// Migrations changed on disk after they were applied, their hash is not replaced.
+    pub modified: Vec<String>,
+}
+
+impl<'a> RehashPlan<'a> {
+    /// Compare hashes of core table with hashes of migrations on disk.
+    /// Rows without migration on disk and rows with the new hash are not changed.
+    pub fn new(
+        datasource: &str,
+        migrations: &[&'a Migration],
+        applied: &[AppliedMigration],
+    ) -> Self {
+        let mut updates = Vec::<&Migration>::new();
+        let mut modified = Vec::<String>::new();
+        for row in applied.iter() {
+            match migrations.iter().find(|m| m.name == row.name) {
+                Some(m) if m.hash == row.hash => {}
+                Some(m) if m.legacy_hash == row.hash => updates.push(m),
+                Some(m) => modified.push(m.name.to_owned()),
+                None => {}
+            }
+        }
+        RehashPlan {
+            datasource: datasource.to_owned(),
+            updates,
+            modified,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.updates.is_empty()
+    }
+}
+
+impl Display for RehashPlan<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Datasource: {}", &self.datasource)?;
+        for m in self.updates.iter() {
+            writeln!(f, "  rehash       {}", &m.name)?;
+        }
+        for name in self.modified.iter() {
+            writeln!(f, "  modified     {}", name)?;
+        }
+        write!(
+            f,
+            "Total: {} hash update(s), {} modified migration(s) skipped.",
+            self.updates.len(),
+            self.modified.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod local_test {
+    use crate::changelogs::Migration;
+    use crate::driver::AppliedMigration;
+    use crate::rehash::RehashPlan;
+    use crate::test_utils::MigrationBuilder;
+
+    fn migration(name: &str, hash: &str, legacy_hash: &str) -> Migration {
+        MigrationBuilder::new(name)
+            .hash(hash)
+            .legacy_hash(legacy_hash)
+            .build()
+    }
+
+    fn applied(name: &str, hash: &str) -> AppliedMigration {
+        AppliedMigration {
+            name: name.to_string(),
+            order: 1,
+            hash: hash.to_string(),
+            version: None,
+        }
+    }
+
+    #[test]
+    pub fn rehash_plan() -> anyhow::Result<()> {
+        let m1 = migration("1.init.sql", "sha-a", "md5-a");
+        let m2 = migration("2.update.sql", "sha-b", "md5-b");
+        let m3 = migration("3.view.sql", "sha-c", "md5-c");
+        let migrations = vec![&m1, &m2, &m3];
+        let rows = vec![
+            applied("1.init.sql", "md5-a"),
+            applied("2.update.sql", "sha-b"),
+            applied("3.view.sql", "md5-changed"),
+            applied("0.removed.sql", "md5-d"),
+        ];
+
+        let plan = RehashPlan::new("localhost", &migrations, &rows);
+        assert!(!plan.is_empty());
+        assert_eq!(
+            "Datasource: localhost\n  \
+             rehash       1.init.sql\n  \
+             modified     3.view.sql\n\
+             Total: 1 hash update(s), 1 modified migration(s) skipped.",
+            plan.to_string()
+        );
+
+        // Second run does not change anything.
+        let rows = vec![
+            applied("1.init.sql", "sha-a"),
+            applied("2.update.sql", "sha-b"),
+        ];
+        assert!(RehashPlan::new("localhost", &migrations, &rows).is_empty());
+        Ok(())
+    }
+}
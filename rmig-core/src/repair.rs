@@ -0,0 +1,120 @@
+use crate::changelogs::Migration;
+use crate::status::{MigrationStatus, StatusReport};
+use std::fmt::{Display, Formatter};
+
+/// Changes of core table for one datasource, made by repair command.
+#[derive(Clone, Debug)]
+pub struct RepairPlan<'a> {
+    pub datasource: String,
+    /// Migrations with changed hash, hash of core table is updated.
+    pub updates: Vec<&'a Migration>,
+    /// Rows of core table without migration on disk, they are deleted.
+    pub deletes: Vec<String>,
+}
+
+impl<'a> RepairPlan<'a> {
+    /// Collect changes from status of datasource.
+    /// Migrations with [run_on_change] are pending re-run, their hash is updated by run command.
+    pub fn new(status: &StatusReport, migrations: &[&'a Migration]) -> Self {
+        let mut updates = Vec::<&Migration>::new();
+        let mut deletes = Vec::<String>::new();
+        for row in status.rows.iter() {
+            match row.status {
+                MigrationStatus::ChecksumMismatch => {
+                    if let Some(m) = migrations.iter().find(|m| m.name == row.name) {
+                        updates.push(m)
+                    }
+                }
+                MigrationStatus::MissingOnDisk => deletes.push(row.name.to_owned()),
+                _ => {}
+            }
+        }
+        RepairPlan {
+            datasource: status.datasource.to_owned(),
+            updates,
+            deletes,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.updates.is_empty() && self.deletes.is_empty()
+    }
+}
+
+impl Display for RepairPlan<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Datasource: {}", &self.datasource)?;
+        for m in self.updates.iter() {
+            writeln!(f, "  update hash  {}", &m.name)?;
+        }
+        for name in self.deletes.iter() {
+            writeln!(f, "  delete row   {}", name)?;
+        }
+        write!(
+            f,
+            "Total: {} hash update(s), {} row(s) to delete.",
+            self.updates.len(),
+            self.deletes.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod local_test {
+    use crate::changelogs::{Migration, QueryOptions};
+    use crate::driver::AppliedMigration;
+    use crate::repair::RepairPlan;
+    use crate::status::StatusReport;
+    use crate::test_utils::MigrationBuilder;
+
+    fn migration(name: &str, hash: &str, run_on_change: bool) -> Migration {
+        let mut opts = QueryOptions::default();
+        opts.run_on_change = Some(run_on_change);
+        MigrationBuilder::new(name)
+            .hash(hash)
+            .query_with("select 1;", opts)
+            .build()
+    }
+
+    fn applied(name: &str, hash: &str) -> AppliedMigration {
+        AppliedMigration {
+            name: name.to_string(),
+            order: 1,
+            hash: hash.to_string(),
+            version: None,
+        }
+    }
+
+    #[test]
+    pub fn repair_plan() -> anyhow::Result<()> {
+        let m1 = migration("1.init.sql", "a", false);
+        let m2 = migration("2.update.sql", "b", false);
+        let m3 = migration("3.view.sql", "c", true);
+        let migrations = vec![&m1, &m2, &m3];
+        let status = StatusReport::compare(
+            "localhost",
+            migrations.clone(),
+            vec![
+                applied("1.init.sql", "a"),
+                applied("2.update.sql", "changed"),
+                applied("3.view.sql", "changed"),
+                applied("0.removed.sql", "d"),
+            ],
+        );
+
+        let plan = RepairPlan::new(&status, &migrations);
+        assert!(!plan.is_empty());
+        assert_eq!(
+            "Datasource: localhost\n  \
+             update hash  2.update.sql\n  \
+             delete row   0.removed.sql\n\
+             Total: 1 hash update(s), 1 row(s) to delete.",
+            plan.to_string()
+        );
+
+        let status = status.without_missing_on_disk();
+        let plan = RepairPlan::new(&status, &[&m1, &m3]);
+        assert!(plan.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,244 @@
+use crate::error::Error;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MigrationOutcome {
+    /// Migration has been executed and added to core table.
+    Applied,
+    /// Migration already exists in core table.
+    Skipped,
+    /// Undo script has been executed and row is deleted from core table.
+    RolledBack,
+    /// Row is added to core table from history of other migration tool, migration is not executed.
+    Imported,
+    /// Row is added to core table by baseline, migration is not executed.
+    Baselined,
+    /// Hash of core table is updated by repair, migration is not executed.
+    Repaired,
+    /// Row of migration, which does not exist on disk, is deleted from core table by repair.
+    Removed,
+    /// Hash of previous versions in core table is replaced by rehash, migration is not executed.
+    Rehashed,
+    /// Migration is not applied, include error message.
+    Failed(String),
+}
+
+impl MigrationOutcome {
+    pub fn label(&self) -> &'static str {
+        match self {
+            MigrationOutcome::Applied => "applied",
+            MigrationOutcome::Skipped => "skipped",
+            MigrationOutcome::RolledBack => "rolled back",
+            MigrationOutcome::Imported => "imported",
+            MigrationOutcome::Baselined => "baselined",
+            MigrationOutcome::Repaired => "repaired",
+            MigrationOutcome::Removed => "removed",
+            MigrationOutcome::Rehashed => "rehashed",
+            MigrationOutcome::Failed(_) => "failed",
+        }
+    }
+
+    pub fn is_failed(&self) -> bool {
+        matches!(self, MigrationOutcome::Failed(_))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MigrationReport {
+    pub name: String,
+    pub outcome: MigrationOutcome,
+    pub duration: Duration,
+}
+
+/// Result of datasource pipeline.
+#[derive(Clone, Debug)]
+pub struct DatasourceReport {
+    pub datasource: String,
+    pub migrations: Vec<MigrationReport>,
+    /// Pipeline error, not related to migration (connection, lock and etc.)
+    pub error: Option<String>,
+}
+
+impl DatasourceReport {
+    pub fn new(datasource: &str) -> Self {
+        DatasourceReport {
+            datasource: datasource.to_owned(),
+            migrations: vec![],
+            error: None,
+        }
+    }
+
+    pub fn with_error(mut self, error: Error) -> Self {
+        self.error = Some(error.to_string());
+        self
+    }
+
+    /// Error of pipeline is added to report, migrations are kept.
+    pub fn with_result(self, result: anyhow::Result<(), Error>) -> Self {
+        match result {
+            Ok(_) => self,
+            Err(e) => self.with_error(e),
+        }
+    }
+
+    pub fn add(&mut self, name: &str, outcome: MigrationOutcome, duration: Duration) {
+        self.migrations.push(MigrationReport {
+            name: name.to_owned(),
+            outcome,
+            duration,
+        });
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.error.is_none() && !self.migrations.iter().any(|m| m.outcome.is_failed())
+    }
+
+    fn count(&self, label: &str) -> usize {
+        self.migrations
+            .iter()
+            .filter(|m| m.outcome.label() == label)
+            .count()
+    }
+}
+
+/// Result of run command for all datasources.
+#[derive(Clone, Debug, Default)]
+pub struct RunReport {
+    pub datasources: Vec<DatasourceReport>,
+}
+
+impl RunReport {
+    pub fn is_success(&self) -> bool {
+        self.datasources.iter().all(|d| d.is_success())
+    }
+
+    /// Process exit code: 0 - all datasources success, 1 - one of them is failed.
+    pub fn exit_code(&self) -> i32 {
+        if self.is_success() {
+            0
+        } else {
+            1
+        }
+    }
+
+    /// Ok, if all datasources success, or else [Error::DatasourcesFailed] with failed datasource names.
+    pub fn into_result(self) -> anyhow::Result<(), Error> {
+        if self.is_success() {
+            return Ok(());
+        }
+        let failed = self
+            .datasources
+            .iter()
+            .filter(|d| !d.is_success())
+            .map(|d| d.datasource.to_owned())
+            .collect::<Vec<String>>();
+        Err(Error::DatasourcesFailed(failed.join(", ")))
+    }
+}
+
+impl Display for RunReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Run report:")?;
+        for ds in self.datasources.iter() {
+            let status = if ds.is_success() { "success" } else { "failed" };
+            writeln!(f, "Datasource {}: {}.", &ds.datasource, status)?;
+            for m in ds.migrations.iter() {
+                writeln!(
+                    f,
+                    "  {:<11} {:>8}ms  {}",
+                    m.outcome.label(),
+                    m.duration.as_millis(),
+                    &m.name
+                )?;
+                if let MigrationOutcome::Failed(e) = &m.outcome {
+                    writeln!(f, "      {}", e)?;
+                }
+            }
+            if let Some(e) = ds.error.as_ref() {
+                writeln!(f, "  {}", e)?;
+            }
+        }
+        let sum = |label: &str| -> usize { self.datasources.iter().map(|d| d.count(label)).sum() };
+        let success = self.datasources.iter().filter(|d| d.is_success()).count();
+        write!(
+            f,
+            "Total: {} applied, {} skipped, {} failed",
+            sum("applied"),
+            sum("skipped"),
+            sum("failed")
+        )?;
+        // Outcomes of rollback, import-history, baseline, repair and rehash commands.
+        for label in [
+            "rolled back",
+            "imported",
+            "baselined",
+            "repaired",
+            "removed",
+            "rehashed",
+        ]
+        .iter()
+        {
+            if sum(label) > 0 {
+                write!(f, ", {} {}", sum(label), label)?;
+            }
+        }
+        write!(
+            f,
+            ". Datasources: {} success, {} failed.",
+            success,
+            self.datasources.len() - success
+        )
+    }
+}
+
+#[cfg(test)]
+mod local_test {
+    use crate::error::Error;
+    use crate::report::{DatasourceReport, MigrationOutcome, RunReport};
+    use std::time::Duration;
+
+    #[test]
+    pub fn run_report_result() -> anyhow::Result<()> {
+        let mut success = DatasourceReport::new("first");
+        success.add(
+            "1.init.sql",
+            MigrationOutcome::Applied,
+            Duration::from_millis(5),
+        );
+        success.add(
+            "2.init.sql",
+            MigrationOutcome::Skipped,
+            Duration::from_millis(0),
+        );
+
+        let mut failed = DatasourceReport::new("second");
+        failed.add(
+            "1.init.sql",
+            MigrationOutcome::Failed("Sql error.".to_string()),
+            Duration::from_millis(1),
+        );
+
+        let aborted = DatasourceReport::new("third")
+            .with_error(Error::ConnectionValidationError("Refused.".to_string()));
+
+        let report = RunReport {
+            datasources: vec![success.clone()],
+        };
+        assert_eq!(0, report.exit_code());
+        assert!(report.into_result().is_ok());
+
+        let report = RunReport {
+            datasources: vec![success, failed, aborted],
+        };
+        assert_eq!(1, report.exit_code());
+        assert!(report
+            .to_string()
+            .ends_with("Total: 1 applied, 1 skipped, 1 failed. Datasources: 1 success, 2 failed."));
+        assert_eq!(
+            Err(Error::DatasourcesFailed("second, third".to_string())),
+            report.into_result()
+        );
+        Ok(())
+    }
+}
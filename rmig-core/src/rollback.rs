@@ -0,0 +1,154 @@
+use crate::changelogs::Migration;
+use crate::driver::AppliedMigration;
+use crate::error::Error;
+use crate::naming::Version;
+use std::str::FromStr;
+
+/// Which applied migrations are rolled back, from the last applied.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RollbackTarget {
+    /// Roll back migrations applied after migration with version or name. Migration itself is kept.
+    To(String),
+    /// Roll back last N applied migrations.
+    Count(usize),
+}
+
+impl RollbackTarget {
+    /// Applied migrations to roll back, in reverse order of execution.
+    /// [applied] is ordered by execution date, as [Driver::find_all_in_core_table] returns.
+    pub fn select(
+        &self,
+        mut applied: Vec<AppliedMigration>,
+    ) -> anyhow::Result<Vec<AppliedMigration>, Error> {
+        applied.reverse();
+        match self {
+            RollbackTarget::Count(count) => {
+                applied.truncate(*count);
+                Ok(applied)
+            }
+            RollbackTarget::To(to) => {
+                let position = applied
+                    .iter()
+                    .position(|a| is_target(a, to))
+                    .ok_or_else(|| {
+                        Error::RollbackError(format!("Migration {} is not applied.", to))
+                    })?;
+                applied.truncate(position);
+                Ok(applied)
+            }
+        }
+    }
+}
+
+/// Migration is matched by version, full name or file name. Row written before core table has
+/// column VERSION is matched by order.
+fn is_target(applied: &AppliedMigration, to: &str) -> bool {
+    let by_version = match &applied.version {
+        Some(version) => Version::parse(to).map_or(false, |v| v == *version),
+        None => i64::from_str(to).map_or(false, |order| order == applied.order),
+    };
+    by_version || applied.name == to || applied.name.ends_with(&*format!("/{}", to))
+}
+
+/// Migration from changelog with undo script for every applied migration, in the same order.
+pub fn find_down_migrations<'a>(
+    targets: &[AppliedMigration],
+    migrations: &[&'a Migration],
+) -> anyhow::Result<Vec<&'a Migration>, Error> {
+    targets
+        .iter()
+        .map(|a| {
+            let m = migrations
+                .iter()
+                .find(|m| m.name == a.name)
+                .ok_or_else(|| {
+                    Error::RollbackError(format!("Migration {} is not found on disk.", &a.name))
+                })?;
+            if m.down_query_list.is_empty() {
+                return Err(Error::RollbackError(format!(
+                    "Migration {} has no undo script.",
+                    &a.name
+                )));
+            }
+            Ok(*m)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod local_test {
+    use crate::driver::AppliedMigration;
+    use crate::error::Error;
+    use crate::naming::Version;
+    use crate::rollback::RollbackTarget;
+
+    fn applied(name: &str, order: i64) -> AppliedMigration {
+        AppliedMigration {
+            name: name.to_string(),
+            order,
+            hash: "md5".to_string(),
+            version: None,
+        }
+    }
+
+    #[test]
+    pub fn select_targets() -> anyhow::Result<()> {
+        let rows = vec![
+            applied("./migration/1.init.sql", 1),
+            applied("./migration/2.update.sql", 2),
+            applied("./migration/3.new.sql", 3),
+        ];
+
+        let orders = |targets: Vec<AppliedMigration>| -> Vec<i64> {
+            targets.iter().map(|a| a.order).collect()
+        };
+
+        assert_eq!(
+            vec![3, 2],
+            orders(RollbackTarget::Count(2).select(rows.clone())?)
+        );
+        assert_eq!(
+            vec![3, 2, 1],
+            orders(RollbackTarget::Count(5).select(rows.clone())?)
+        );
+        assert_eq!(
+            vec![3, 2],
+            orders(RollbackTarget::To("1".to_string()).select(rows.clone())?)
+        );
+        assert_eq!(
+            vec![3],
+            orders(RollbackTarget::To("2.update.sql".to_string()).select(rows.clone())?)
+        );
+        assert_eq!(
+            Err(Error::RollbackError(
+                "Migration 4 is not applied.".to_string()
+            )),
+            RollbackTarget::To("4".to_string()).select(rows)
+        );
+
+        // Flyway versions with the same order are matched by full version.
+        let rows = ["1.1", "1.2", "2"]
+            .iter()
+            .map(|v| AppliedMigration {
+                version: Version::parse(v),
+                ..applied(&*format!("./migration/V{}__flyway.sql", v), 1)
+            })
+            .collect::<Vec<AppliedMigration>>();
+        let versions = |targets: Vec<AppliedMigration>| -> Vec<String> {
+            targets
+                .iter()
+                .map(|a| a.version.as_ref().unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(
+            vec!["2", "1.2"],
+            versions(RollbackTarget::To("1.1".to_string()).select(rows.clone())?)
+        );
+        assert_eq!(
+            vec!["2"],
+            versions(RollbackTarget::To("1_2".to_string()).select(rows.clone())?)
+        );
+        assert!(RollbackTarget::To("1".to_string()).select(rows).is_err());
+        Ok(())
+    }
+}
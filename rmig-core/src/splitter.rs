@@ -0,0 +1,269 @@
+use crate::enum_str;
+use crate::error::Error;
+use std::str::FromStr;
+
+/// Property of changelog or env [--env/-e], which enables built-in splitter of migration files.
+pub const QUERY_SPLITTER: &str = "query_splitter";
+
+enum_str! {
+    pub enum Dialect {
+        Postgres = 0x00000,
+        Mysql = 0x00001,
+        Oracle = 0x00002,
+        Sqlite = 0x00003,
+    }
+}
+
+impl FromStr for Dialect {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let dialect = s.trim().to_lowercase();
+        vec![
+            Dialect::Postgres,
+            Dialect::Mysql,
+            Dialect::Oracle,
+            Dialect::Sqlite,
+        ]
+        .into_iter()
+        .find(|d| d.name().to_lowercase().eq(&dialect))
+        .ok_or_else(|| {
+            Error::ParseFileError(format!(
+                "Unknown {} '{}'. Use postgres, mysql, oracle or sqlite.",
+                QUERY_SPLITTER, s
+            ))
+        })
+    }
+}
+
+/// Split text of migration on statements by dialect.
+/// Terminators inside of string literals, quoted identifiers, comments and Postgres dollar-quoted
+/// bodies are ignored. MySQL [DELIMITER] command changes terminator, Oracle [/] line terminates
+/// PL/SQL block. Statements are returned without terminator, PL/SQL blocks are returned as is.
+pub fn split(sql: &str, dialect: &Dialect) -> Vec<String> {
+    let bytes = sql.as_bytes();
+    let mut statements = Vec::<String>::new();
+    let mut delimiter = ";".to_string();
+    let mut start = 0usize;
+    let mut i = 0usize;
+
+    while i < bytes.len() {
+        // Client commands are written on own line.
+        if i == 0 || bytes[i - 1] == b'\n' {
+            let line_end = sql[i..].find('\n').map_or(bytes.len(), |n| i + n + 1);
+            let line = sql[i..line_end].trim();
+            let is_delimiter = line
+                .get(..10)
+                .map_or(false, |c| c.eq_ignore_ascii_case("DELIMITER "));
+            if (*dialect == Dialect::Mysql && is_delimiter)
+                || (*dialect == Dialect::Oracle && line == "/")
+            {
+                push_statement(&mut statements, &sql[start..i]);
+                if is_delimiter {
+                    delimiter = line[10..].trim().to_string();
+                }
+                i = line_end;
+                start = i;
+                continue;
+            }
+        }
+
+        match bytes[i] {
+            b'-' if bytes.get(i + 1) == Some(&b'-') => i = skip_line(sql, i),
+            b'#' if *dialect == Dialect::Mysql => i = skip_line(sql, i),
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = sql[i + 2..].find("*/").map_or(bytes.len(), |n| i + n + 4)
+            }
+            b'\'' => i = skip_quoted(bytes, i, *dialect == Dialect::Mysql),
+            b'"' => i = skip_quoted(bytes, i, *dialect == Dialect::Mysql),
+            b'`' if *dialect == Dialect::Mysql => i = skip_quoted(bytes, i, false),
+            b'$' if *dialect == Dialect::Postgres => i = skip_dollar_quoted(sql, i),
+            _ if bytes[i..].starts_with(delimiter.as_bytes())
+                && is_terminator(&sql[start..i], dialect, &*delimiter) =>
+            {
+                push_statement(&mut statements, &sql[start..i]);
+                i += delimiter.len();
+                start = i;
+            }
+            _ => i += 1,
+        }
+    }
+    push_statement(&mut statements, &sql[start..]);
+    statements
+}
+
+/// Anonymous block or stored unit declaration.
+pub(crate) fn is_plsql_block(statement: &str) -> bool {
+    let words = statement
+        .split_whitespace()
+        .take(6)
+        .map(|w| w.to_uppercase())
+        .collect::<Vec<String>>();
+    match words.first().map(|w| w.as_str()) {
+        Some("BEGIN") | Some("DECLARE") => true,
+        Some("CREATE") => words
+            .iter()
+            .skip(1)
+            .find(|w| !["OR", "REPLACE", "EDITIONABLE", "NONEDITIONABLE"].contains(&w.as_str()))
+            .map(|w| ["PROCEDURE", "FUNCTION", "PACKAGE", "TRIGGER", "TYPE"].contains(&w.as_str()))
+            .unwrap_or(false),
+        _ => false,
+    }
+}
+
+/// Semicolon inside of PL/SQL block (Oracle) or trigger body (SQLite) does not terminate statement.
+fn is_terminator(statement: &str, dialect: &Dialect, delimiter: &str) -> bool {
+    if delimiter != ";" {
+        return true;
+    }
+    let statement = without_leading_comments(statement);
+    match dialect {
+        Dialect::Oracle => !is_plsql_block(statement),
+        Dialect::Sqlite => {
+            let words = statement
+                .split_whitespace()
+                .take(3)
+                .map(|w| w.to_uppercase())
+                .collect::<Vec<String>>();
+            let is_trigger = words.first().map(|w| w.as_str()) == Some("CREATE")
+                && words.iter().skip(1).any(|w| w == "TRIGGER");
+            !is_trigger || statement.trim_end().to_uppercase().ends_with("END")
+        }
+        _ => true,
+    }
+}
+
+/// Statement without leading line comments ([--] or [#] of MySQL).
+fn without_leading_comments(statement: &str) -> &str {
+    let mut statement = statement.trim_start();
+    while statement.starts_with("--") || statement.starts_with('#') {
+        statement = statement
+            .find('\n')
+            .map_or("", |n| statement[n + 1..].trim_start());
+    }
+    statement
+}
+
+fn push_statement(statements: &mut Vec<String>, statement: &str) {
+    let statement = statement.trim();
+    if !without_leading_comments(statement).is_empty() {
+        statements.push(statement.to_string());
+    }
+}
+
+/// Position after the end of line.
+fn skip_line(sql: &str, i: usize) -> usize {
+    sql[i..].find('\n').map_or(sql.len(), |n| i + n + 1)
+}
+
+/// Position after closing quote. Quote is escaped by doubling, or by backslash for MySQL.
+fn skip_quoted(bytes: &[u8], i: usize, backslash: bool) -> usize {
+    let quote = bytes[i];
+    let mut j = i + 1;
+    while j < bytes.len() {
+        if backslash && bytes[j] == b'\\' {
+            j += 2;
+        } else if bytes[j] == quote && bytes.get(j + 1) == Some(&quote) {
+            j += 2;
+        } else if bytes[j] == quote {
+            return j + 1;
+        } else {
+            j += 1;
+        }
+    }
+    bytes.len()
+}
+
+/// Position after closing tag of dollar-quoted body [$tag$ ... $tag$]. Parameters like [$1] are skipped.
+fn skip_dollar_quoted(sql: &str, i: usize) -> usize {
+    let bytes = sql.as_bytes();
+    let mut j = i + 1;
+    while j < bytes.len() && (bytes[j].is_ascii_alphanumeric() || bytes[j] == b'_') {
+        j += 1;
+    }
+    if j >= bytes.len() || bytes[j] != b'$' || bytes[i + 1].is_ascii_digit() {
+        return i + 1;
+    }
+    let tag = &sql[i..=j];
+    sql[j + 1..]
+        .find(tag)
+        .map_or(bytes.len(), |n| j + 1 + n + tag.len())
+}
+
+#[cfg(test)]
+mod local_test {
+    use crate::splitter::{split, Dialect};
+    use std::str::FromStr;
+
+    #[test]
+    pub fn split_postgres() -> anyhow::Result<()> {
+        let statements = split(
+            "-- comment; with semicolon\n\
+             create table a(id int, name text default 'a;b');\n\
+             create function f() returns int as $body$ begin return 1; end; $body$ language plpgsql;\n\
+             /* block; comment */ select $$;$$, \"a;b\" from a where id = $1;\n\
+             -- trailing comment",
+            &Dialect::from_str("postgres")?,
+        );
+        assert_eq!(3, statements.len());
+        assert_eq!(
+            "-- comment; with semicolon\ncreate table a(id int, name text default 'a;b')",
+            statements[0]
+        );
+        assert!(statements[1].ends_with("$body$ language plpgsql"));
+        assert_eq!(
+            "/* block; comment */ select $$;$$, \"a;b\" from a where id = $1",
+            statements[2]
+        );
+        Ok(())
+    }
+
+    #[test]
+    pub fn split_mysql() -> anyhow::Result<()> {
+        let statements = split(
+            "insert into a values ('it\\'s;');\n\
+             # comment;\n\
+             DELIMITER //\n\
+             create procedure p() begin select 1; select 2; end//\n\
+             DELIMITER ;\n\
+             select `a;b` from a;",
+            &Dialect::Mysql,
+        );
+        assert_eq!(3, statements.len());
+        assert_eq!("insert into a values ('it\\'s;')", statements[0]);
+        // Comment before DELIMITER is not a statement.
+        assert_eq!(
+            "create procedure p() begin select 1; select 2; end",
+            statements[1]
+        );
+        assert_eq!("select `a;b` from a", statements[2]);
+        Ok(())
+    }
+
+    #[test]
+    pub fn split_oracle_and_sqlite() -> anyhow::Result<()> {
+        let statements = split(
+            "CREATE TABLE A(ID NUMBER);\n\
+             BEGIN\n  INSERT INTO A VALUES (1);\nEND;\n/\n\
+             INSERT INTO A VALUES (2);",
+            &Dialect::Oracle,
+        );
+        assert_eq!(3, statements.len());
+        assert_eq!("CREATE TABLE A(ID NUMBER)", statements[0]);
+        assert_eq!("BEGIN\n  INSERT INTO A VALUES (1);\nEND;", statements[1]);
+
+        let statements = split(
+            "create table a(id int);\n\
+             create trigger t after insert on a begin update a set id = 1; end;\n\
+             insert into a values (1);",
+            &Dialect::Sqlite,
+        );
+        assert_eq!(3, statements.len());
+        assert_eq!(
+            "create trigger t after insert on a begin update a set id = 1; end",
+            statements[1]
+        );
+        assert!(Dialect::from_str("db2").is_err());
+        Ok(())
+    }
+}
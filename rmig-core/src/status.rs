@@ -0,0 +1,182 @@
+use crate::changelogs::Migration;
+use crate::driver::AppliedMigration;
+use crate::enum_str;
+use crate::plan::Step;
+use std::fmt::{Display, Formatter};
+
+enum_str! {
+    pub enum MigrationStatus {
+        Applied = 0x00000,
+        Pending = 0x00001,
+        ChecksumMismatch = 0x00002,
+        MissingOnDisk = 0x00003,
+        PendingRerun = 0x00004,
+    }
+}
+
+impl MigrationStatus {
+    /// Human readable status for status table.
+    pub fn label(&self) -> &'static str {
+        match self {
+            MigrationStatus::Applied => "applied",
+            MigrationStatus::Pending => "pending",
+            MigrationStatus::ChecksumMismatch => "checksum mismatch",
+            MigrationStatus::MissingOnDisk => "missing on disk",
+            MigrationStatus::PendingRerun => "pending re-run",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct StatusRow {
+    pub status: MigrationStatus,
+    pub order: i64,
+    pub name: String,
+}
+
+/// Comparison migrations from changelog with rows in core table for one datasource.
+#[derive(Clone)]
+pub struct StatusReport {
+    pub datasource: String,
+    pub rows: Vec<StatusRow>,
+}
+
+impl StatusReport {
+    /// Compare migrations (by name and hash) with applied rows, as run command does.
+    /// Applied migrations with [run_always], or [run_on_change] with changed hash, are pending re-run.
+    /// Migrations keep changelog order, rows found only in core table are added at the end.
+    pub fn compare(
+        datasource: &str,
+        migrations: Vec<&Migration>,
+        applied: Vec<AppliedMigration>,
+    ) -> Self {
+        let mut rows = Vec::<StatusRow>::with_capacity(migrations.len());
+
+        for m in migrations.iter() {
+            let status = match Step::of(m, &applied) {
+                Ok(Step::Skip) => MigrationStatus::Applied,
+                Ok(Step::New) => MigrationStatus::Pending,
+                Ok(Step::Repeat) | Ok(Step::Changed) => MigrationStatus::PendingRerun,
+                Err(_) => MigrationStatus::ChecksumMismatch,
+            };
+            rows.push(StatusRow {
+                status,
+                order: m.order,
+                name: m.name.to_owned(),
+            });
+        }
+
+        for a in applied {
+            if !migrations.iter().any(|m| m.name.eq(&a.name)) {
+                rows.push(StatusRow {
+                    status: MigrationStatus::MissingOnDisk,
+                    order: a.order,
+                    name: a.name,
+                });
+            }
+        }
+
+        StatusReport {
+            datasource: datasource.to_owned(),
+            rows,
+        }
+    }
+
+    /// Drop rows which exist only in core table.
+    /// Used when changelog was filtered by stage and other stages are not read from disk.
+    pub fn without_missing_on_disk(mut self) -> Self {
+        self.rows
+            .retain(|r| r.status != MigrationStatus::MissingOnDisk);
+        self
+    }
+
+    pub fn count(&self, status: MigrationStatus) -> usize {
+        self.rows.iter().filter(|r| r.status == status).count()
+    }
+}
+
+impl Display for StatusReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Datasource: {}", &self.datasource)?;
+        writeln!(f, "{:<18} {:>6}  {}", "STATUS", "ORDER", "FILENAME")?;
+        for row in self.rows.iter() {
+            writeln!(
+                f,
+                "{:<18} {:>6}  {}",
+                row.status.label(),
+                row.order,
+                &row.name
+            )?;
+        }
+        write!(
+            f,
+            "Total: {} applied, {} pending, {} pending re-run, {} checksum mismatch, {} missing on disk.",
+            self.count(MigrationStatus::Applied),
+            self.count(MigrationStatus::Pending),
+            self.count(MigrationStatus::PendingRerun),
+            self.count(MigrationStatus::ChecksumMismatch),
+            self.count(MigrationStatus::MissingOnDisk)
+        )
+    }
+}
+
+#[cfg(test)]
+mod local_test {
+    use crate::changelogs::{Migration, QueryOptions};
+    use crate::driver::AppliedMigration;
+    use crate::status::{MigrationStatus, StatusReport};
+    use crate::test_utils::MigrationBuilder;
+
+    fn migration(name: &str, order: i64, hash: &str) -> Migration {
+        MigrationBuilder::new(name).order(order).hash(hash).build()
+    }
+
+    fn applied(name: &str, order: i64, hash: &str) -> AppliedMigration {
+        AppliedMigration {
+            name: name.to_string(),
+            order,
+            hash: hash.to_string(),
+            version: None,
+        }
+    }
+
+    #[test]
+    pub fn compare_status() -> anyhow::Result<()> {
+        let m1 = migration("1.init.sql", 1, "a");
+        let m2 = migration("2.update.sql", 2, "b");
+        let m3 = migration("3.new.sql", 3, "c");
+        let mut opts = QueryOptions::default();
+        opts.run_on_change = Some(true);
+        let m4 = MigrationBuilder::new("4.view.sql")
+            .order(4)
+            .hash("e")
+            .query_with("create or replace view v as select 1;", opts)
+            .build();
+        let report = StatusReport::compare(
+            "localhost",
+            vec![&m1, &m2, &m3, &m4],
+            vec![
+                applied("1.init.sql", 1, "a"),
+                applied("2.update.sql", 2, "changed"),
+                applied("4.view.sql", 4, "changed"),
+                applied("0.removed.sql", 0, "d"),
+            ],
+        );
+
+        assert_eq!(5, report.rows.len());
+        assert!(report.rows[0].status == MigrationStatus::Applied);
+        assert!(report.rows[1].status == MigrationStatus::ChecksumMismatch);
+        assert!(report.rows[2].status == MigrationStatus::Pending);
+        assert!(report.rows[3].status == MigrationStatus::PendingRerun);
+        assert!(report.rows[4].status == MigrationStatus::MissingOnDisk);
+        assert_eq!("0.removed.sql", report.rows[4].name.as_str());
+        assert!(report.to_string().ends_with(
+            "Total: 1 applied, 1 pending, 1 pending re-run, 1 checksum mismatch, 1 missing on disk."
+        ));
+
+        let report = report.without_missing_on_disk();
+        assert_eq!(0, report.count(MigrationStatus::MissingOnDisk));
+        assert_eq!(4, report.rows.len());
+        Ok(())
+    }
+}
@@ -54,14 +54,15 @@ mod local_test {
     #[test]
     fn test_init_sql() -> anyhow::Result<()> {
         let value = include_str!("init/pg_init.sql");
-        let mut result = TeraManager::new(HashMap::new())
+        let mut env = HashMap::<String, String>::new();
+        env.insert(String::from("HISTORY_TABLE"), String::from("CHANGELOGS"));
+        let mut result = TeraManager::new(env.clone())
             .apply("pg_init.sql", value)?
             .trim()
             .to_owned();
         println!("Result: {}", &result);
         assert!(&result.starts_with("CREATE TABLE IF NOT EXISTS CHANGELOGS"));
 
-        let mut env = HashMap::<String, String>::new();
         env.insert(String::from("SCHEMA_ADMIN"), String::from("WORLD"));
         result = TeraManager::new(env)
             .apply("pg_init.sql", value)?
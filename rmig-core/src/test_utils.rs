@@ -4,6 +4,12 @@ use log4rs::Config;
 use log4rs::config::{Appender, Root};
 use log::LevelFilter;
 use crate::error::Error;
+#[cfg(test)]
+use crate::changelogs::{Migration, Query, QueryOptions};
+#[cfg(test)]
+use crate::naming::Version;
+#[cfg(test)]
+use std::path::PathBuf;
 
 pub fn init_logger() -> RmigEmptyResult {
     let stdout = ConsoleAppender::builder().target(Target::Stdout).build();
@@ -22,4 +28,125 @@ pub fn init_logger() -> RmigEmptyResult {
     // once you are done.
     log4rs::init_config(config).map_err(|e| Error::LoggerConfigurationError(e.to_string()))?;
     Ok(())
-}
\ No newline at end of file
+}
+/// Migration of tests, fields which are not set have values of migration without options.
+#[cfg(test)]
+pub struct MigrationBuilder {
+    migration: Migration,
+}
+
+#[cfg(test)]
+impl MigrationBuilder {
+    /// Migration with order 1 and hash [md5].
+    pub fn new(name: &str) -> Self {
+        MigrationBuilder {
+            migration: Migration {
+                name: name.to_string(),
+                hash: "md5".to_string(),
+                legacy_hash: "md5".to_string(),
+                separator: "-->".to_string(),
+                order: 1,
+                version: Version::parse("1"),
+                query_list: Default::default(),
+                down_query_list: Default::default(),
+                stage: None,
+                author: None,
+                options: None,
+            },
+        }
+    }
+
+    /// Order and version of default naming.
+    pub fn order(mut self, order: i64) -> Self {
+        self.migration.order = order;
+        self.migration.version = Version::parse(&*order.to_string());
+        self
+    }
+
+    /// Order and version of Flyway naming.
+    pub fn version(mut self, version: &str) -> Self {
+        self.migration.version = Version::parse(version);
+        self.migration.order = self.migration.version.as_ref().map_or(0, |v| v.major());
+        self
+    }
+
+    /// Hash and legacy hash, as migration hashed by md5 without normalization.
+    pub fn hash(mut self, hash: &str) -> Self {
+        self.migration.hash = hash.to_string();
+        self.migration.legacy_hash = hash.to_string();
+        self
+    }
+
+    pub fn legacy_hash(mut self, legacy_hash: &str) -> Self {
+        self.migration.legacy_hash = legacy_hash.to_string();
+        self
+    }
+
+    pub fn query(self, query: &str) -> Self {
+        self.query_with(query, Default::default())
+    }
+
+    pub fn query_with(mut self, query: &str, opts: QueryOptions) -> Self {
+        self.migration.query_list.push_back(Query {
+            query: query.to_string(),
+            opts,
+        });
+        self
+    }
+
+    pub fn down_query(mut self, query: &str) -> Self {
+        self.migration.down_query_list.push_back(Query {
+            query: query.to_string(),
+            opts: Default::default(),
+        });
+        self
+    }
+
+    pub fn stage(mut self, stage: &str) -> Self {
+        self.migration.stage = Some(stage.to_string());
+        self
+    }
+
+    pub fn build(self) -> Migration {
+        self.migration
+    }
+}
+
+/// Temporary directory of test, it is removed on drop, so failed assertion does not leave it.
+#[cfg(test)]
+pub struct TempDir {
+    pub path: PathBuf,
+}
+
+#[cfg(test)]
+impl TempDir {
+    /// Empty directory [prefix_pid] in temporary directory of system.
+    pub fn new(prefix: &str) -> std::io::Result<Self> {
+        let path = std::env::temp_dir().join(format!("{}_{}", prefix, std::process::id()));
+        if path.exists() {
+            std::fs::remove_dir_all(&path)?;
+        }
+        std::fs::create_dir_all(&path)?;
+        Ok(TempDir { path })
+    }
+
+    /// Write file by path relative to directory, parent directories are created.
+    pub fn write(&self, path: &str, text: &str) -> std::io::Result<PathBuf> {
+        let file = self.path.join(path);
+        std::fs::create_dir_all(file.parent().unwrap())?;
+        std::fs::write(&file, text)?;
+        Ok(file)
+    }
+
+    /// Glob of all files and directories in directory.
+    pub fn glob(&self) -> String {
+        format!("{}/*", self.path.display())
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
@@ -3,7 +3,7 @@ macro_rules! enum_str {
     (pub enum $name:ident {
         $($variant:ident = $val:expr),*,
     }) => {
-        #[derive(Clone, Eq, PartialEq)]
+        #[derive(Clone, Debug, Eq, PartialEq)]
         pub enum $name {
             $($variant = $val),*
         }
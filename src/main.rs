@@ -2,6 +2,6 @@ use rmig_core::cli::AppRmigCli;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    AppRmigCli::default().init().execute().await?;
+    AppRmigCli::default().init()?.execute().await?;
     Ok(())
 }